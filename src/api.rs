@@ -1,12 +1,29 @@
 use crate::app::state::State;
-use crate::chat::{Conversation, Message};
+use crate::chat::{Conversation, Message, Metadata, Summary};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use strum::EnumIter;
 
 pub mod anthropic;
+mod capabilities;
 pub mod openai;
+mod tokens;
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+pub use capabilities::{Capabilities, Capability};
+pub use tokens::{count_tokens, ContextUsage, TokenCounter};
+
+const TITLE_MAX_TOKENS: u32 = 32;
+const TITLE_MAX_CONTEXT_CHARS: usize = 2000;
+const TITLE_INSTRUCTIONS: &str = "Write a short title (at most 6 words) for the conversation \
+    given by the user. Respond with the title only, without quotes or punctuation at the end.";
+const SUMMARY_MAX_TOKENS: u32 = 1024;
+const MAX_TOKENS_STEP: u32 = 1024;
+const MODELS_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const SUMMARY_INSTRUCTIONS: &str = "Summarize the conversation given by the user, including any \
+    previous summary. Keep all facts, decisions and code details that may be needed to continue \
+    the conversation. Respond with the summary only.";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Provider {
     #[serde(rename = "openai")]
     OpenAi,
@@ -26,10 +43,51 @@ pub struct CompletionResponse {
     pub usage: TokenUsage,
 }
 
+/// A model listed by the models endpoint of a provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteModel {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
 pub struct TokenUsage {
+    /// Input tokens, including cached tokens.
     pub prompt: u32,
     pub completion: u32,
     pub total: u32,
+    #[serde(default)]
+    pub cached: u32,
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.prompt = self.prompt.saturating_add(other.prompt);
+        self.completion = self.completion.saturating_add(other.completion);
+        self.total = self.total.saturating_add(other.total);
+        self.cached = self.cached.saturating_add(other.cached);
+    }
+}
+
+/// Model prices in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pricing {
+    pub input: f64,
+    pub output: f64,
+    /// Price of cached input tokens, defaults to the input price.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cached_input: Option<f64>,
+}
+
+impl Pricing {
+    #[must_use]
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        let cached = f64::from(usage.cached.min(usage.prompt));
+        let uncached = f64::from(usage.prompt.saturating_sub(usage.cached));
+        let completion = f64::from(usage.completion);
+        let cached_input = self.cached_input.unwrap_or(self.input);
+        (uncached * self.input + cached * cached_input + completion * self.output) / 1_000_000.0
+    }
 }
 
 impl std::fmt::Display for TokenUsage {
@@ -38,10 +96,231 @@ impl std::fmt::Display for TokenUsage {
             f,
             "Tokens: {} [{} prompt, {} completion]",
             self.total, self.prompt, self.completion
-        )
+        )?;
+        if self.cached > 0 {
+            write!(f, " [{} cached]", self.cached)?;
+        }
+        Ok(())
+    }
+}
+
+/// Model parameters sent with a completion request.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Parameters {
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+}
+
+impl Parameters {
+    /// Get the parameters with overrides from `self` and missing values from the defaults.
+    #[must_use]
+    pub fn with_defaults(&self, defaults: &Self) -> Self {
+        Self {
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            frequency_penalty: self.frequency_penalty.or(defaults.frequency_penalty),
+            presence_penalty: self.presence_penalty.or(defaults.presence_penalty),
+        }
+    }
+
+    #[must_use]
+    pub fn is_set(&self, parameter: Parameter) -> bool {
+        match parameter {
+            Parameter::MaxTokens => self.max_tokens.is_some(),
+            Parameter::Temperature => self.temperature.is_some(),
+            Parameter::TopP => self.top_p.is_some(),
+            Parameter::FrequencyPenalty => self.frequency_penalty.is_some(),
+            Parameter::PresencePenalty => self.presence_penalty.is_some(),
+        }
+    }
+
+    #[must_use]
+    pub fn get_display(&self, parameter: Parameter) -> Option<String> {
+        match parameter {
+            Parameter::MaxTokens => self.max_tokens.map(|value| value.to_string()),
+            Parameter::Temperature => self.temperature.map(|value| format!("{value:.2}")),
+            Parameter::TopP => self.top_p.map(|value| format!("{value:.2}")),
+            Parameter::FrequencyPenalty => {
+                self.frequency_penalty.map(|value| format!("{value:.2}"))
+            }
+            Parameter::PresencePenalty => self.presence_penalty.map(|value| format!("{value:.2}")),
+        }
+    }
+
+    pub fn clear(&mut self, parameter: Parameter) {
+        match parameter {
+            Parameter::MaxTokens => self.max_tokens = None,
+            Parameter::Temperature => self.temperature = None,
+            Parameter::TopP => self.top_p = None,
+            Parameter::FrequencyPenalty => self.frequency_penalty = None,
+            Parameter::PresencePenalty => self.presence_penalty = None,
+        }
+    }
+
+    /// Increase or decrease the parameter by a step within the range supported by the provider,
+    /// starting from the default if not set.
+    pub fn step(
+        &mut self,
+        parameter: Parameter,
+        provider: Provider,
+        defaults: &Self,
+        increase: bool,
+    ) {
+        let range = parameter.range(provider);
+        let current = self.with_defaults(defaults);
+        match parameter {
+            Parameter::MaxTokens => {
+                let value = current.max_tokens.unwrap_or(MAX_TOKENS_STEP);
+                self.max_tokens = Some(if increase {
+                    value.saturating_add(MAX_TOKENS_STEP)
+                } else {
+                    value.saturating_sub(MAX_TOKENS_STEP).max(MAX_TOKENS_STEP)
+                });
+            }
+            Parameter::Temperature => {
+                let value = current.temperature.unwrap_or(1.0);
+                self.temperature = Some(step_float(value, 0.1, range, increase));
+            }
+            Parameter::TopP => {
+                let value = current.top_p.unwrap_or(1.0);
+                self.top_p = Some(step_float(value, 0.05, range, increase));
+            }
+            Parameter::FrequencyPenalty => {
+                let value = current.frequency_penalty.unwrap_or(0.0);
+                self.frequency_penalty = Some(step_float(value, 0.1, range, increase));
+            }
+            Parameter::PresencePenalty => {
+                let value = current.presence_penalty.unwrap_or(0.0);
+                self.presence_penalty = Some(step_float(value, 0.1, range, increase));
+            }
+        }
+    }
+}
+
+/// Step a value and round it to avoid accumulating floating point errors.
+fn step_float(value: f32, step: f32, (min, max): (f32, f32), increase: bool) -> f32 {
+    let value = if increase { value + step } else { value - step };
+    (value.clamp(min, max) * 100.0).round() / 100.0
+}
+
+/// Serialize using the shortest decimal representation, widening to f64 directly would write
+/// values like 0.699999988079071 to the model files.
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn serialize_f32<S: serde::Serializer>(value: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+    let shortest = value.to_string().parse().unwrap_or(f64::from(*value));
+    serializer.serialize_f64(shortest)
+}
+
+/// A model parameter that can be overridden per conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Parameter {
+    MaxTokens,
+    Temperature,
+    TopP,
+    FrequencyPenalty,
+    PresencePenalty,
+}
+
+impl Parameter {
+    #[must_use]
+    pub fn is_supported(self, provider: Provider) -> bool {
+        match provider {
+            Provider::OpenAi => true,
+            Provider::Anthropic => !matches!(self, Self::FrequencyPenalty | Self::PresencePenalty),
+        }
+    }
+
+    /// Minimum and maximum values accepted by the provider API, max tokens is not bounded.
+    #[must_use]
+    pub fn range(self, provider: Provider) -> (f32, f32) {
+        match (self, provider) {
+            (Self::Temperature, Provider::OpenAi) => (0.0, 2.0),
+            (Self::Temperature | Self::TopP, _) => (0.0, 1.0),
+            (Self::FrequencyPenalty | Self::PresencePenalty, _) => (-2.0, 2.0),
+            (Self::MaxTokens, _) => (0.0, f32::MAX),
+        }
+    }
+}
+
+impl std::fmt::Display for Parameter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::MaxTokens => "max_tokens",
+            Self::Temperature => "temperature",
+            Self::TopP => "top_p",
+            Self::FrequencyPenalty => "frequency_penalty",
+            Self::PresencePenalty => "presence_penalty",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::fmt::Display for Parameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parameters = [
+            ("temperature", self.temperature),
+            ("top_p", self.top_p),
+            ("frequency_penalty", self.frequency_penalty),
+            ("presence_penalty", self.presence_penalty),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| format!("{name} {value}")))
+        .chain(self.max_tokens.map(|value| format!("max_tokens {value}")))
+        .collect::<Vec<String>>();
+        write!(f, "{}", parameters.join(", "))
+    }
+}
+
+/// Id and context window of the model used for completions with the active provider.
+#[must_use]
+pub fn get_active_model_context(state: &State) -> Option<(String, Option<u32>)> {
+    match state.config.provider {
+        Provider::OpenAi => state
+            .models
+            .openai
+            .first()
+            .map(|model| (model.id.clone(), model.context_window)),
+        Provider::Anthropic => state
+            .models
+            .anthropic
+            .first()
+            .map(|model| (model.id.clone(), model.context_window)),
     }
 }
 
+/// Parameters of the model used for completions with the active provider.
+#[must_use]
+pub fn get_active_model_parameters(state: &State) -> Parameters {
+    match state.config.provider {
+        Provider::OpenAi => state.models.openai.first().map(openai::Model::parameters),
+        Provider::Anthropic => state
+            .models
+            .anthropic
+            .first()
+            .map(anthropic::Model::parameters),
+    }
+    .unwrap_or_default()
+}
+
+/// Capabilities of the model used for completions with the active provider.
+#[must_use]
+pub fn get_active_model_capabilities(state: &State) -> Capabilities {
+    match state.config.provider {
+        Provider::OpenAi => state.models.openai.first().map(|model| model.capabilities),
+        Provider::Anthropic => state
+            .models
+            .anthropic
+            .first()
+            .map(|model| model.capabilities),
+    }
+    .unwrap_or_default()
+}
+
 pub async fn get_completion(
     state: &State,
     conversation: &Conversation,
@@ -69,3 +348,221 @@ pub async fn get_completion(
         }
     }
 }
+
+/// Models available from the provider API.
+///
+/// The returned future does not borrow the state, so the models can be fetched in a background
+/// task.
+pub fn get_remote_models(
+    state: &State,
+    provider: Provider,
+) -> impl std::future::Future<Output = Result<Vec<RemoteModel>>> + Send + 'static {
+    let keys = (
+        state.config.keys.openai.clone(),
+        state.config.keys.anthropic.clone(),
+    );
+    async move {
+        match provider {
+            Provider::OpenAi => openai::get_remote_models(&keys.0)
+                .await
+                .context("get openai models"),
+            Provider::Anthropic => anthropic::get_remote_models(&keys.1)
+                .await
+                .context("get anthropic models"),
+        }
+    }
+}
+
+/// Send a models request with a timeout and get the response text, failing on error statuses.
+async fn get_models_response_text(request: reqwest::RequestBuilder) -> Result<String> {
+    let response = request
+        .timeout(MODELS_REQUEST_TIMEOUT)
+        .send()
+        .await
+        .context("send models request")?;
+    let status = response.status();
+    let text = response.text().await.context("get models response text")?;
+    if !status.is_success() {
+        anyhow::bail!("models request failed with status {status}: {text}");
+    }
+    Ok(text)
+}
+
+/// A completion request with a token limit, owning its model and key so it can be sent without
+/// borrowing the state.
+enum CappedRequest {
+    OpenAi {
+        key: String,
+        model: openai::Model,
+    },
+    Anthropic {
+        key: String,
+        model: anthropic::Model,
+    },
+}
+
+impl CappedRequest {
+    /// Use the model with the given id (or the first model) of the active provider.
+    fn new(state: &State, model_ids: (&str, &str), max_tokens: u32) -> Result<Self> {
+        let (openai_model_id, anthropic_model_id) = model_ids;
+        match state.config.provider {
+            Provider::OpenAi => {
+                let mut model = state
+                    .models
+                    .openai
+                    .iter()
+                    .find(|model| model.id == openai_model_id)
+                    .or_else(|| state.models.openai.first())
+                    .context("no models configured for OpenAI")?
+                    .clone();
+                model.max_completion_tokens = max_tokens;
+                Ok(Self::OpenAi {
+                    key: state.config.keys.openai.clone(),
+                    model,
+                })
+            }
+            Provider::Anthropic => {
+                let mut model = state
+                    .models
+                    .anthropic
+                    .iter()
+                    .find(|model| model.id == anthropic_model_id)
+                    .or_else(|| state.models.anthropic.first())
+                    .context("no models configured for Anthropic")?
+                    .clone();
+                model.max_tokens = max_tokens;
+                Ok(Self::Anthropic {
+                    key: state.config.keys.anthropic.clone(),
+                    model,
+                })
+            }
+        }
+    }
+
+    async fn send(&self, conversation: &Conversation) -> Result<CompletionResponse> {
+        match self {
+            Self::OpenAi { key, model } => openai::get_completion(key, model, conversation)
+                .await
+                .context("get openai completion"),
+            Self::Anthropic { key, model } => anthropic::get_completion(key, model, conversation)
+                .await
+                .context("get anthropic completion"),
+        }
+    }
+}
+
+/// Generate a title for the conversation using a cheap completion.
+///
+/// The returned future does not borrow the state, so the title can be generated in a background
+/// task. It resolves to the title and the metadata of the completion that generated it.
+pub fn generate_title(
+    state: &State,
+    conversation: &Conversation,
+) -> Result<impl std::future::Future<Output = Result<(String, Metadata)>> + Send + 'static> {
+    let excerpt: String = conversation
+        .to_string()
+        .chars()
+        .take(TITLE_MAX_CONTEXT_CHARS)
+        .collect();
+    let mut title_conversation = Conversation::new(TITLE_INSTRUCTIONS.to_owned());
+    title_conversation.add_message(Message::new_user_message(excerpt));
+    let model_ids = (
+        state.config.titles.openai_model.as_str(),
+        state.config.titles.anthropic_model.as_str(),
+    );
+    let request = CappedRequest::new(state, model_ids, TITLE_MAX_TOKENS)?;
+    Ok(async move {
+        let response = request
+            .send(&title_conversation)
+            .await
+            .context("get title completion")?;
+        let title = response
+            .message
+            .content
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or_default()
+            .trim()
+            .trim_matches(['"', '\'', '*', '#', ' '])
+            .trim_end_matches('.')
+            .to_owned();
+        if title.is_empty() {
+            anyhow::bail!("empty title response");
+        }
+        Ok((title, response.message.metadata))
+    })
+}
+
+/// Summarize the first messages of the conversation, extending the existing summary.
+///
+/// Returns the summary and the metadata of the completion that generated it.
+pub async fn generate_summary(
+    state: &State,
+    conversation: &Conversation,
+    message_count: usize,
+) -> Result<(Summary, Metadata)> {
+    let summarized_count = conversation
+        .summary
+        .as_ref()
+        .map_or(0, |summary| summary.message_count.min(message_count));
+    let messages = conversation
+        .messages
+        .get(summarized_count..message_count)
+        .context("summary message range out of bounds")?;
+    let text = conversation
+        .summary
+        .iter()
+        .map(|summary| format!("Previous summary: {}", summary.text))
+        .chain(
+            messages
+                .iter()
+                .filter(|message| message.included)
+                .map(ToString::to_string),
+        )
+        .collect::<Vec<String>>()
+        .join("\n\n");
+    let mut summary_conversation = Conversation::new(SUMMARY_INSTRUCTIONS.to_owned());
+    summary_conversation.add_message(Message::new_user_message(text));
+    let model_ids = (
+        state.config.context.openai_summary_model.as_str(),
+        state.config.context.anthropic_summary_model.as_str(),
+    );
+    let response = CappedRequest::new(state, model_ids, SUMMARY_MAX_TOKENS)?
+        .send(&summary_conversation)
+        .await
+        .context("get summary completion")?;
+    let summary = Summary {
+        text: response.message.content.trim().to_owned(),
+        message_count,
+    };
+    Ok((summary, response.message.metadata))
+}
+
+#[cfg(test)]
+mod parameters_tests {
+    use super::{Parameter, Parameters, Provider};
+
+    #[test]
+    fn step_parameters() {
+        let defaults = Parameters {
+            max_tokens: Some(2048),
+            temperature: Some(1.95),
+            ..Default::default()
+        };
+        let mut overrides = Parameters::default();
+        let openai = Provider::OpenAi;
+        overrides.step(Parameter::Temperature, openai, &defaults, true);
+        assert_eq!(overrides.temperature, Some(2.0));
+        overrides.step(Parameter::Temperature, openai, &defaults, false);
+        assert_eq!(overrides.temperature, Some(1.9));
+        overrides.step(Parameter::Temperature, Provider::Anthropic, &defaults, true);
+        assert_eq!(overrides.temperature, Some(1.0));
+        overrides.step(Parameter::MaxTokens, openai, &defaults, false);
+        overrides.step(Parameter::MaxTokens, openai, &defaults, false);
+        assert_eq!(overrides.max_tokens, Some(1024));
+        overrides.step(Parameter::FrequencyPenalty, openai, &defaults, false);
+        assert_eq!(overrides.frequency_penalty, Some(-0.1));
+        overrides.clear(Parameter::Temperature);
+        assert_eq!(overrides.with_defaults(&defaults).temperature, Some(1.95));
+    }
+}
@@ -1,10 +1,17 @@
-use crate::api::{CompletionResponse, TokenUsage};
-use crate::chat::{Conversation, Message as GenericMessage, Role as GenericRole};
+use crate::api::{
+    count_tokens, get_models_response_text, serialize_f32, Capabilities, CompletionResponse,
+    Parameter, Parameters, Pricing, Provider, RemoteModel, TokenUsage,
+};
+use crate::chat::{
+    Conversation, Message as GenericMessage, Metadata as GenericMetadata, Role as GenericRole,
+};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 const API_ENDPOINT: &str = "https://api.anthropic.com/v1/messages";
+const MODELS_ENDPOINT: &str = "https://api.anthropic.com/v1/models?limit=1000";
 const MODEL_VERSION: &str = "2023-06-01";
+const OMITTED_MESSAGES_PLACEHOLDER: &str = "[Earlier messages omitted]";
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
@@ -12,12 +19,51 @@ pub struct Config {
     pub models: Vec<Model>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Model {
     pub id: String,
     pub name: String,
     pub max_tokens: u32,
+    #[serde(serialize_with = "serialize_f32")]
     pub temperature: f32,
+    /// Maximum number of tokens in a request, used for warnings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<Pricing>,
+    #[serde(default, skip_serializing_if = "Capabilities::is_default")]
+    pub capabilities: Capabilities,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self {
+            id: "new-model".to_owned(),
+            name: "New model".to_owned(),
+            max_tokens: 4096,
+            temperature: 1.0,
+            context_window: None,
+            pricing: None,
+            capabilities: Capabilities::default(),
+        }
+    }
+}
+
+impl Model {
+    #[must_use]
+    pub fn parameters(&self) -> Parameters {
+        Parameters {
+            max_tokens: Some(self.max_tokens),
+            temperature: Some(self.temperature),
+            ..Default::default()
+        }
+    }
+
+    /// Set the model defaults from the parameters, missing parameters are left unchanged.
+    pub fn set_parameters(&mut self, parameters: &Parameters) {
+        self.max_tokens = parameters.max_tokens.unwrap_or(self.max_tokens);
+        self.temperature = parameters.temperature.unwrap_or(self.temperature);
+    }
 }
 
 impl std::fmt::Display for Model {
@@ -31,23 +77,74 @@ struct Request {
     messages: Vec<Message>,
     model: String,
     max_tokens: u32,
-    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "String::is_empty")]
     system: String,
 }
 
 impl Request {
     fn new(model: &Model, conversation: &Conversation) -> Self {
-        let messages = conversation
-            .messages
-            .iter()
-            .map(std::convert::Into::into)
-            .collect();
+        let capabilities = &model.capabilities;
+        let system_instructions = conversation.context_system_instructions();
+        let context_messages = conversation
+            .context_messages(|text| count_tokens(Provider::Anthropic, &model.id, text));
+        // Without system prompt support the instructions are sent as the first user message
+        let instructions_message = (!capabilities.system_role).then(|| Message {
+            role: Role::User,
+            content: system_instructions.clone(),
+        });
+        // Messages must alternate between roles and start with a user message, which may not be
+        // the case when messages are excluded or pinned
+        let mut messages: Vec<Message> = Vec::new();
+        for message in instructions_message
+            .into_iter()
+            .chain(context_messages.into_iter().map(Message::from))
+        {
+            match messages.last_mut() {
+                Some(previous) if previous.role == message.role => {
+                    previous.content.push_str("\n\n");
+                    previous.content.push_str(&message.content);
+                }
+                None if message.role == Role::Assistant => {
+                    messages.push(Message {
+                        role: Role::User,
+                        content: OMITTED_MESSAGES_PLACEHOLDER.to_owned(),
+                    });
+                    messages.push(message);
+                }
+                _ => messages.push(message),
+            }
+        }
+        let overrides = &conversation.parameters;
+        // Anthropic recommends altering only one of temperature and top_p, newer models reject
+        // requests with both, so a top_p override replaces the temperature
+        let top_p = overrides
+            .top_p
+            .filter(|_| capabilities.supports(Parameter::TopP));
         Request {
             messages,
             model: model.id.clone(),
-            max_tokens: model.max_tokens,
-            temperature: model.temperature,
-            system: conversation.system_instructions.clone(),
+            max_tokens: overrides.max_tokens.unwrap_or(model.max_tokens),
+            temperature: (capabilities.supports(Parameter::Temperature) && top_p.is_none())
+                .then(|| overrides.temperature.unwrap_or(model.temperature)),
+            top_p,
+            system: if capabilities.system_role {
+                system_instructions
+            } else {
+                String::new()
+            },
+        }
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters {
+            max_tokens: Some(self.max_tokens),
+            temperature: self.temperature,
+            top_p: self.top_p,
+            ..Default::default()
         }
     }
 }
@@ -97,17 +194,23 @@ struct ResponseContent {
 }
 
 #[derive(Deserialize, Debug)]
+#[allow(clippy::struct_field_names)]
 struct ResponseUsage {
+    /// Input tokens that were not read from the cache.
     pub input_tokens: u32,
     pub output_tokens: u32,
+    #[serde(default)]
+    pub cache_read_input_tokens: u32,
 }
 
 impl From<ResponseUsage> for TokenUsage {
     fn from(value: ResponseUsage) -> Self {
+        let prompt = value.input_tokens + value.cache_read_input_tokens;
         TokenUsage {
-            prompt: value.input_tokens,
+            prompt,
             completion: value.output_tokens,
-            total: value.input_tokens + value.output_tokens,
+            total: prompt + value.output_tokens,
+            cached: value.cache_read_input_tokens,
         }
     }
 }
@@ -132,6 +235,7 @@ pub async fn get_completion(
 ) -> Result<CompletionResponse> {
     let client = reqwest::Client::new();
     let call_data = Request::new(model, conversation);
+    let request_start = std::time::Instant::now();
     let raw_response = client
         .post(API_ENDPOINT)
         .header("x-api-key", key)
@@ -144,23 +248,60 @@ pub async fn get_completion(
         .text()
         .await
         .context("parse api response as json")?;
+    let latency = request_start.elapsed();
     let parsed_response = serde_json::from_str::<Response>(&raw_response)
         .with_context(|| format!("failed to parse response: {raw_response}"))?;
     if parsed_response.role != Role::Assistant {
         anyhow::bail!("unexpected non-assistant role response");
-    };
+    }
     let message_content = &parsed_response
         .content
         .first()
         .context("missing response choices")?
         .text;
-    let message = GenericMessage {
-        role: GenericRole::Assistant(model.to_string()),
-        content: message_content.clone(),
-    };
-    let response = CompletionResponse {
-        message,
-        usage: parsed_response.usage.into(),
+    let usage: TokenUsage = parsed_response.usage.into();
+    let metadata = GenericMetadata {
+        created_at: Some(chrono::Utc::now()),
+        provider: Some(Provider::Anthropic),
+        model_id: Some(parsed_response.model.clone()),
+        parameters: Some(call_data.parameters()),
+        usage: Some(usage),
+        latency_ms: u64::try_from(latency.as_millis()).ok(),
+        cost: model.pricing.map(|pricing| pricing.cost(&usage)),
     };
+    let message =
+        GenericMessage::new_assistant_message(model.to_string(), message_content.clone(), metadata);
+    let response = CompletionResponse { message, usage };
     Ok(response)
 }
+
+#[derive(Deserialize, Debug)]
+struct ModelsResponse {
+    data: Vec<ModelsResponseModel>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ModelsResponseModel {
+    id: String,
+    display_name: String,
+}
+
+/// Models available to the API key, newest first.
+pub async fn get_remote_models(key: &str) -> Result<Vec<RemoteModel>> {
+    let client = reqwest::Client::new();
+    let request = client
+        .get(MODELS_ENDPOINT)
+        .header("x-api-key", key)
+        .header("anthropic-version", MODEL_VERSION);
+    let raw_response = get_models_response_text(request).await?;
+    let parsed_response = serde_json::from_str::<ModelsResponse>(&raw_response)
+        .with_context(|| format!("failed to parse models response: {raw_response}"))?;
+    Ok(parsed_response
+        .data
+        .into_iter()
+        .map(|model| RemoteModel {
+            id: model.id,
+            name: model.display_name,
+        })
+        .collect())
+}
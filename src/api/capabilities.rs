@@ -0,0 +1,96 @@
+use crate::api::Parameter;
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+
+/// Features supported by a model, consulted by the request builders.
+///
+/// Only flags that differ from the defaults are serialized, as written in the models files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Capabilities {
+    /// System instructions can be sent with the system role.
+    #[serde(skip_serializing_if = "is_true")]
+    pub system_role: bool,
+    /// System instructions can be sent with the developer role, preferred over the system role.
+    #[serde(skip_serializing_if = "is_false")]
+    pub developer_role: bool,
+    /// Sampling parameters like temperature and `top_p` are accepted.
+    #[serde(skip_serializing_if = "is_true")]
+    pub temperature: bool,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            system_role: true,
+            developer_role: false,
+            temperature: true,
+        }
+    }
+}
+
+impl Capabilities {
+    #[must_use]
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    #[must_use]
+    pub fn get(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::SystemRole => self.system_role,
+            Capability::DeveloperRole => self.developer_role,
+            Capability::Temperature => self.temperature,
+        }
+    }
+
+    pub fn toggle(&mut self, capability: Capability) {
+        let flag = match capability {
+            Capability::SystemRole => &mut self.system_role,
+            Capability::DeveloperRole => &mut self.developer_role,
+            Capability::Temperature => &mut self.temperature,
+        };
+        *flag = !*flag;
+    }
+
+    /// Check if the model accepts the parameter, max tokens are always accepted.
+    #[must_use]
+    pub fn supports(&self, parameter: Parameter) -> bool {
+        match parameter {
+            Parameter::MaxTokens => true,
+            Parameter::Temperature
+            | Parameter::TopP
+            | Parameter::FrequencyPenalty
+            | Parameter::PresencePenalty => self.temperature,
+        }
+    }
+}
+
+/// A single capability flag of a model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Capability {
+    SystemRole,
+    DeveloperRole,
+    Temperature,
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::SystemRole => "system_role",
+            Self::DeveloperRole => "developer_role",
+            Self::Temperature => "temperature",
+        };
+        write!(f, "{name}")
+    }
+}
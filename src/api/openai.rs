@@ -1,17 +1,27 @@
-use crate::api::{CompletionResponse, TokenUsage};
-use crate::chat::{Conversation, Message as GenericMessage, Role as GenericRole};
+use crate::api::{
+    count_tokens, get_models_response_text, serialize_f32, Capabilities, CompletionResponse,
+    Parameter, Parameters, Pricing, Provider, RemoteModel, TokenUsage,
+};
+use crate::chat::{
+    Conversation, Message as GenericMessage, Metadata as GenericMetadata, Role as GenericRole,
+};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
 const API_ENDPOINT: &str = "https://api.openai.com/v1/chat/completions";
-
-#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum ModelClass {
-    #[default]
-    Classic,
-    Reasoning,
-}
+const MODELS_ENDPOINT: &str = "https://api.openai.com/v1/models";
+/// Models listed by the API that do not support chat completions.
+const NON_CHAT_MODEL_MARKERS: [&str; 9] = [
+    "audio",
+    "dall-e",
+    "embedding",
+    "image",
+    "moderation",
+    "realtime",
+    "search",
+    "transcribe",
+    "tts",
+];
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
@@ -19,16 +29,67 @@ pub struct Config {
     pub models: Vec<Model>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Model {
     pub id: String,
     pub name: String,
-    pub class: Option<ModelClass>,
     pub max_completion_tokens: u32,
+    #[serde(serialize_with = "serialize_f32")]
     pub temperature: f32,
+    #[serde(serialize_with = "serialize_f32")]
     pub top_p: f32,
+    #[serde(serialize_with = "serialize_f32")]
     pub frequency_penalty: f32,
+    #[serde(serialize_with = "serialize_f32")]
     pub presence_penalty: f32,
+    /// Maximum number of tokens in a request, used for warnings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<Pricing>,
+    #[serde(default, skip_serializing_if = "Capabilities::is_default")]
+    pub capabilities: Capabilities,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Self {
+            id: "new-model".to_owned(),
+            name: "New model".to_owned(),
+            max_completion_tokens: 4096,
+            temperature: 1.0,
+            top_p: 1.0,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            context_window: None,
+            pricing: None,
+            capabilities: Capabilities::default(),
+        }
+    }
+}
+
+impl Model {
+    #[must_use]
+    pub fn parameters(&self) -> Parameters {
+        Parameters {
+            max_tokens: Some(self.max_completion_tokens),
+            temperature: Some(self.temperature),
+            top_p: Some(self.top_p),
+            frequency_penalty: Some(self.frequency_penalty),
+            presence_penalty: Some(self.presence_penalty),
+        }
+    }
+
+    /// Set the model defaults from the parameters, missing parameters are left unchanged.
+    pub fn set_parameters(&mut self, parameters: &Parameters) {
+        self.max_completion_tokens = parameters.max_tokens.unwrap_or(self.max_completion_tokens);
+        self.temperature = parameters.temperature.unwrap_or(self.temperature);
+        self.top_p = parameters.top_p.unwrap_or(self.top_p);
+        self.frequency_penalty = parameters
+            .frequency_penalty
+            .unwrap_or(self.frequency_penalty);
+        self.presence_penalty = parameters.presence_penalty.unwrap_or(self.presence_penalty);
+    }
 }
 
 impl std::fmt::Display for Model {
@@ -42,34 +103,70 @@ struct Request {
     messages: Vec<Message>,
     model: String,
     max_completion_tokens: u32,
-    top_p: f32,
-    temperature: f32,
-    frequency_penalty: f32,
-    presence_penalty: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
 }
 
 impl Request {
     fn new(model: &Model, conversation: &Conversation) -> Self {
-        let instruction_role = match model.class.unwrap_or_default() {
-            ModelClass::Classic => Role::System,
-            // we use the 'user' role because it seems some o1 models do not
-            // support developer/system instructions yet
-            ModelClass::Reasoning => Role::User,
+        let capabilities = &model.capabilities;
+        let instructions_role = if capabilities.developer_role {
+            Role::Developer
+        } else if capabilities.system_role {
+            Role::System
+        } else {
+            Role::User
         };
         let system_message = Message {
-            role: instruction_role,
-            content: conversation.system_instructions.clone(),
+            role: instructions_role,
+            content: conversation.context_system_instructions(),
         };
+        let context_messages =
+            conversation.context_messages(|text| count_tokens(Provider::OpenAi, &model.id, text));
         let mut messages = vec![system_message];
-        messages.extend(&mut conversation.messages.iter().map(std::convert::Into::into));
+        messages.extend(context_messages.into_iter().map(std::convert::Into::into));
+        let overrides = &conversation.parameters;
+        let sampling = |parameter: Parameter, value: Option<f32>, default: f32| {
+            capabilities
+                .supports(parameter)
+                .then(|| value.unwrap_or(default))
+        };
         Self {
             messages,
             model: model.id.clone(),
-            max_completion_tokens: model.max_completion_tokens,
-            temperature: model.temperature,
-            top_p: model.top_p,
-            frequency_penalty: model.frequency_penalty,
-            presence_penalty: model.presence_penalty,
+            max_completion_tokens: overrides.max_tokens.unwrap_or(model.max_completion_tokens),
+            temperature: sampling(
+                Parameter::Temperature,
+                overrides.temperature,
+                model.temperature,
+            ),
+            top_p: sampling(Parameter::TopP, overrides.top_p, model.top_p),
+            frequency_penalty: sampling(
+                Parameter::FrequencyPenalty,
+                overrides.frequency_penalty,
+                model.frequency_penalty,
+            ),
+            presence_penalty: sampling(
+                Parameter::PresencePenalty,
+                overrides.presence_penalty,
+                model.presence_penalty,
+            ),
+        }
+    }
+
+    fn parameters(&self) -> Parameters {
+        Parameters {
+            max_tokens: Some(self.max_completion_tokens),
+            temperature: self.temperature,
+            top_p: self.top_p,
+            frequency_penalty: self.frequency_penalty,
+            presence_penalty: self.presence_penalty,
         }
     }
 }
@@ -79,6 +176,7 @@ impl Request {
 enum Role {
     User,
     System,
+    Developer,
     Assistant,
 }
 
@@ -127,6 +225,14 @@ struct ResponseUsage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
+    #[serde(default)]
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PromptTokensDetails {
+    #[serde(default)]
+    pub cached_tokens: u32,
 }
 
 impl From<ResponseUsage> for TokenUsage {
@@ -135,6 +241,9 @@ impl From<ResponseUsage> for TokenUsage {
             prompt: value.prompt_tokens,
             completion: value.completion_tokens,
             total: value.total_tokens,
+            cached: value
+                .prompt_tokens_details
+                .map_or(0, |details| details.cached_tokens),
         }
     }
 }
@@ -158,6 +267,7 @@ pub async fn get_completion(
 ) -> Result<CompletionResponse> {
     let client = reqwest::Client::new();
     let call_data = Request::new(model, conversation);
+    let request_start = std::time::Instant::now();
     let raw_response = client
         .post(API_ENDPOINT)
         .bearer_auth(key)
@@ -168,6 +278,7 @@ pub async fn get_completion(
         .text()
         .await
         .context("parse api response as json")?;
+    let latency = request_start.elapsed();
     let parsed_response = serde_json::from_str::<Response>(&raw_response)
         .with_context(|| format!("failed to parse response: {raw_response}"))?;
     let message = &parsed_response
@@ -177,14 +288,99 @@ pub async fn get_completion(
         .message;
     if message.role != Role::Assistant {
         anyhow::bail!("unexpected non-assistant role response");
+    }
+    let usage: TokenUsage = parsed_response.usage.into();
+    let metadata = GenericMetadata {
+        created_at: Some(chrono::Utc::now()),
+        provider: Some(Provider::OpenAi),
+        model_id: Some(parsed_response.model.clone()),
+        parameters: Some(call_data.parameters()),
+        usage: Some(usage),
+        latency_ms: u64::try_from(latency.as_millis()).ok(),
+        cost: model.pricing.map(|pricing| pricing.cost(&usage)),
     };
-    let generic_message = GenericMessage {
-        role: GenericRole::Assistant(model.to_string()),
-        content: message.content.clone(),
-    };
+    let generic_message =
+        GenericMessage::new_assistant_message(model.to_string(), message.content.clone(), metadata);
     let response = CompletionResponse {
         message: generic_message,
-        usage: parsed_response.usage.into(),
+        usage,
     };
     Ok(response)
 }
+
+#[derive(Deserialize, Debug)]
+struct ModelsResponse {
+    data: Vec<ModelsResponseModel>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ModelsResponseModel {
+    id: String,
+}
+
+/// Chat models available to the API key.
+pub async fn get_remote_models(key: &str) -> Result<Vec<RemoteModel>> {
+    let client = reqwest::Client::new();
+    let raw_response =
+        get_models_response_text(client.get(MODELS_ENDPOINT).bearer_auth(key)).await?;
+    let parsed_response = serde_json::from_str::<ModelsResponse>(&raw_response)
+        .with_context(|| format!("failed to parse models response: {raw_response}"))?;
+    let mut models: Vec<RemoteModel> = parsed_response
+        .data
+        .into_iter()
+        .filter(|model| is_chat_model(&model.id))
+        .map(|model| RemoteModel {
+            name: model.id.clone(),
+            id: model.id,
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(models)
+}
+
+fn is_chat_model(id: &str) -> bool {
+    let is_chat_family =
+        id.starts_with("gpt-") || id.starts_with("chatgpt-") || is_reasoning_model_id(id);
+    is_chat_family
+        && !NON_CHAT_MODEL_MARKERS
+            .iter()
+            .any(|marker| id.contains(marker))
+}
+
+/// Check if the model id belongs to the o-series reasoning models.
+#[must_use]
+pub fn is_reasoning_model_id(id: &str) -> bool {
+    id.starts_with('o') && id.chars().nth(1).is_some_and(|char| char.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod request_tests {
+    use super::{Model, Request, Role};
+    use crate::api::Capabilities;
+    use crate::chat::{Conversation, Message};
+
+    #[test]
+    fn capabilities() {
+        let mut conversation = Conversation::new("instructions".to_owned());
+        conversation.add_message(Message::new_user_message("hello".to_owned()));
+        let mut model = Model::default();
+        let request = Request::new(&model, &conversation);
+        assert_eq!(request.messages[0].role, Role::System);
+        assert_eq!(request.temperature, Some(1.0));
+
+        model.capabilities = Capabilities {
+            developer_role: true,
+            temperature: false,
+            ..Capabilities::default()
+        };
+        let request = Request::new(&model, &conversation);
+        assert_eq!(request.messages[0].role, Role::Developer);
+        assert_eq!(request.temperature, None);
+        assert_eq!(request.top_p, None);
+
+        model.capabilities.developer_role = false;
+        model.capabilities.system_role = false;
+        let request = Request::new(&model, &conversation);
+        assert_eq!(request.messages[0].role, Role::User);
+    }
+}
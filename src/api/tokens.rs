@@ -0,0 +1,154 @@
+use crate::api::Provider;
+use crate::chat::Conversation;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+
+/// Tokens used by the role and formatting of each message.
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+/// Tokens used to prime the assistant reply.
+const REPLY_OVERHEAD_TOKENS: usize = 3;
+/// Anthropic does not publish a tokenizer, their guidance is roughly 3.5 characters per token.
+/// We round down to overestimate rather than underestimate.
+const ANTHROPIC_CHARS_PER_TOKEN: usize = 3;
+const MAX_CACHED_TEXTS: usize = 1000;
+
+/// Estimated tokens of a request compared to the context window of the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextUsage {
+    pub prompt_tokens: usize,
+    /// Tokens reserved for the reply, the max tokens of the request.
+    pub reply_tokens: u32,
+    pub context_window: Option<u32>,
+}
+
+impl ContextUsage {
+    /// Whether the prompt and the reserved reply tokens exceed the context window.
+    #[must_use]
+    pub fn exceeds_window(&self) -> bool {
+        self.context_window.is_some_and(|context_window| {
+            self.prompt_tokens
+                .saturating_add(self.reply_tokens as usize)
+                > context_window as usize
+        })
+    }
+}
+
+/// Offline estimation of prompt token counts, cached by text.
+#[derive(Debug, Default)]
+pub struct TokenCounter {
+    cache: HashMap<u64, usize>,
+}
+
+impl TokenCounter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Estimate the number of tokens in the text for the given model.
+    pub fn count(&mut self, provider: Provider, model_id: &str, text: &str) -> usize {
+        let mut hasher = DefaultHasher::new();
+        (provider, model_id, text).hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some(count) = self.cache.get(&key) {
+            return *count;
+        }
+        let count = count_tokens(provider, model_id, text);
+        if self.cache.len() >= MAX_CACHED_TEXTS {
+            self.cache.clear();
+        }
+        self.cache.insert(key, count);
+        count
+    }
+
+    /// Estimate the number of prompt tokens of the conversation context, including an additional
+    /// message.
+    pub fn count_conversation(
+        &mut self,
+        provider: Provider,
+        model_id: &str,
+        conversation: &Conversation,
+        additional_message: &str,
+    ) -> usize {
+        let context_start = self.context_start(provider, model_id, conversation);
+        let system_instructions = conversation.context_system_instructions();
+        let texts = std::iter::once(system_instructions.as_str())
+            .chain(
+                conversation
+                    .messages
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| conversation.is_in_context(*index, context_start))
+                    .map(|(_, message)| message.content.as_str()),
+            )
+            .chain((!additional_message.is_empty()).then_some(additional_message));
+        texts
+            .map(|text| self.count(provider, model_id, text) + MESSAGE_OVERHEAD_TOKENS)
+            .sum::<usize>()
+            + REPLY_OVERHEAD_TOKENS
+    }
+
+    /// Index of the first message of the conversation in the context window.
+    pub fn context_start(
+        &mut self,
+        provider: Provider,
+        model_id: &str,
+        conversation: &Conversation,
+    ) -> usize {
+        conversation.context_start(|text| self.count(provider, model_id, text))
+    }
+}
+
+/// Estimate the number of tokens in the text for the given model.
+#[must_use]
+pub fn count_tokens(provider: Provider, model_id: &str, text: &str) -> usize {
+    match provider {
+        Provider::OpenAi => count_openai_tokens(model_id, text),
+        Provider::Anthropic => text.chars().count().div_ceil(ANTHROPIC_CHARS_PER_TOKEN),
+    }
+}
+
+fn count_openai_tokens(model_id: &str, text: &str) -> usize {
+    let bpe = match get_tokenizer(model_id) {
+        Some(Tokenizer::Cl100kBase) => tiktoken_rs::cl100k_base_singleton(),
+        _ => tiktoken_rs::o200k_base_singleton(),
+    };
+    let bpe = bpe.lock();
+    bpe.encode_with_special_tokens(text).len()
+}
+
+#[cfg(test)]
+mod tokens_tests {
+    use super::{ContextUsage, TokenCounter};
+    use crate::api::Provider;
+    use crate::chat::{Conversation, Message};
+
+    #[test]
+    fn count_tokens() {
+        let mut counter = TokenCounter::new();
+        assert_eq!(counter.count(Provider::OpenAi, "gpt-4o", "hello world"), 2);
+        assert_eq!(
+            counter.count(Provider::Anthropic, "claude", "hello world"),
+            4
+        );
+        let mut conversation = Conversation::new("hello world".to_owned());
+        conversation.add_message(Message::new_user_message("hello world".to_owned()));
+        let used = counter.count_conversation(Provider::OpenAi, "gpt-4o", &conversation, "hello");
+        assert_eq!(used, 2 + 4 + 2 + 4 + 1 + 4 + 3);
+    }
+
+    #[test]
+    fn reserve_reply_tokens() {
+        let mut usage = ContextUsage {
+            prompt_tokens: 6000,
+            reply_tokens: 2048,
+            context_window: Some(8192),
+        };
+        assert!(!usage.exceeds_window());
+        usage.reply_tokens = 4096;
+        assert!(usage.exceeds_window());
+        usage.context_window = None;
+        assert!(!usage.exceeds_window());
+    }
+}
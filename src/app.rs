@@ -1,6 +1,7 @@
 pub mod actions;
 pub mod events;
 pub mod focus;
+pub mod highlight;
 pub mod hotkeys;
 pub mod state;
 pub mod ui;
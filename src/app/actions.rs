@@ -1,7 +1,10 @@
 use crate::api::get_completion;
 use crate::app::state::State;
+use crate::chat::{Change, FilePatch, Message, PatchPreview};
+use crate::usage::{format_cost, LimitStatus};
 use anyhow::{Context, Result};
 use std::io::Write;
+use std::path::Path;
 use std::process::Command;
 
 const API_ERROR_FEEDBACK: &str = "An error occured, see debug logs.";
@@ -30,24 +33,92 @@ pub fn get_message_text_from_editor(state: &State, initial_text: &str) -> Result
 }
 
 pub fn edit_config_file_in_editor(state: &State) -> Result<()> {
+    edit_file_in_editor(state, &state.paths.get_config_file())
+}
+
+pub fn edit_file_in_editor(state: &State, file: &Path) -> Result<()> {
     let mut editor_command_iter = state.config.commands.editor.iter();
     Command::new(editor_command_iter.next().context("editor command empty")?)
         .args(editor_command_iter.collect::<Vec<&String>>())
-        .arg(state.paths.get_config_file())
+        .arg(file)
         .status()
         .context("run editor")?;
     Ok(())
 }
 
-pub async fn do_prompt(state: &mut State) -> Result<()> {
+/// Add the message to the active conversation and get a response.
+///
+/// Refuses to send once a spending limit is reached or the context window would be exceeded,
+/// unless the same prompt is sent again to override each of them.
+pub async fn do_prompt(state: &mut State, message: Message) -> Result<()> {
+    let provider = state.config.provider;
+    let prompt_key = state.get_prompt_key();
+    let limit_overridden = state.ui.limit_override.as_ref() == Some(&prompt_key);
+    match state.ledger.check_limits(provider, &state.config.limits) {
+        LimitStatus::Exceeded(reason) if !limit_overridden => {
+            state.ui.limit_override = Some(prompt_key);
+            state.set_status_bar_warning(format!("{reason}. Confirm again to send anyway."));
+            state.add_debug_log(format!("Refused to send message: {reason}"));
+            return Ok(());
+        }
+        LimitStatus::Exceeded(reason) => {
+            state.add_debug_log(format!("Overriding limit: {reason}"));
+        }
+        LimitStatus::Warning(_) | LimitStatus::WithinLimits => (),
+    }
+    if let Some(usage) = state.get_context_usage(&message.content) {
+        let context_window = usage.context_window.unwrap_or_default();
+        if usage.exceeds_window() && state.ui.context_override.as_ref() != Some(&prompt_key) {
+            state.ui.context_override = Some(prompt_key);
+            state.set_status_bar_warning(format!(
+                "Estimated {} prompt tokens and {} reply tokens exceed the context window of \
+                {context_window} tokens. Confirm again to send anyway.",
+                usage.prompt_tokens, usage.reply_tokens
+            ));
+            return Ok(());
+        }
+    }
+    state.ui.limit_override = None;
+    state.ui.context_override = None;
+    state.get_active_conversation_mut()?.add_message(message);
+    let context_start = state.get_context_start();
+    if state
+        .get_active_conversation()?
+        .needs_summary(context_start)
+        && !generate_summary(state, context_start, limit_overridden).await?
+    {
+        // Sending without the summary would silently drop the older messages from the context
+        state.get_active_conversation_mut()?.messages.pop();
+        state.set_status_bar_warning(
+            "Failed to summarize older messages, the message was not sent. See debug logs.",
+        );
+        return Ok(());
+    }
     let raw_response = get_completion(state, state.get_active_conversation()?).await;
     match raw_response {
         Ok(response) => {
+            state.record_usage(&response.message.metadata);
+            let feedback = match response.message.metadata.cost {
+                Some(cost) => format!("{} Cost: {}", response.usage, format_cost(cost)),
+                None => response.usage.to_string(),
+            };
             state
                 .get_active_conversation_mut()?
                 .add_message(response.message);
-            state.set_status_bar_text(format!("AI responded. {}", response.usage));
-            state.add_debug_log(response.usage.to_string());
+            state.add_debug_log(feedback.clone());
+            match state.ledger.check_limits(provider, &state.config.limits) {
+                LimitStatus::Warning(reason) | LimitStatus::Exceeded(reason) => {
+                    state.set_status_bar_warning(format!("AI responded. {reason}."));
+                }
+                LimitStatus::WithinLimits => {
+                    state.set_status_bar_text(format!("AI responded. {feedback}"));
+                }
+            }
+            if state.config.titles.generate && state.get_active_conversation()?.title.is_none() {
+                state
+                    .start_title_generation()
+                    .context("start title generation")?;
+            }
         }
         Err(error) => {
             state.set_status_bar_text(API_ERROR_FEEDBACK);
@@ -57,6 +128,39 @@ pub async fn do_prompt(state: &mut State) -> Result<()> {
     Ok(())
 }
 
+/// Summarize older messages, unless a spending limit is reached and was not overridden.
+///
+/// Returns whether the messages were summarized.
+async fn generate_summary(
+    state: &mut State,
+    message_count: usize,
+    limit_overridden: bool,
+) -> Result<bool> {
+    if let LimitStatus::Exceeded(reason) = state
+        .ledger
+        .check_limits(state.config.provider, &state.config.limits)
+    {
+        if !limit_overridden {
+            state.add_debug_log(format!("Skipped summary: {reason}"));
+            return Ok(false);
+        }
+    }
+    state.set_status_bar_text("Summarizing older messages...");
+    match crate::api::generate_summary(state, state.get_active_conversation()?, message_count).await
+    {
+        Ok((summary, metadata)) => {
+            state.record_usage(&metadata);
+            state.add_debug_log(format!("Summarized {message_count} messages"));
+            state.get_active_conversation_mut()?.summary = Some(summary);
+            Ok(true)
+        }
+        Err(error) => {
+            state.add_debug_log(format!("Failed to summarize messages: {error:?}"));
+            Ok(false)
+        }
+    }
+}
+
 pub fn export_to_clipboard(state: &State, text: &str) -> Result<()> {
     let mut command_iter = state.config.commands.copy.iter();
     let mut child_process = Command::new(command_iter.next().context("clipboard command empty")?)
@@ -76,3 +180,52 @@ pub fn export_to_clipboard(state: &State, text: &str) -> Result<()> {
         .context("wait on clipboard command process")?;
     Ok(())
 }
+
+pub fn open_text_in_editor(state: &State, text: &str, extension: &str) -> Result<()> {
+    let file_path = state.paths.get_code_block_file(extension);
+    std::fs::write(&file_path, text).context("write text to file")?;
+    edit_file_in_editor(state, &file_path)
+}
+
+#[must_use]
+pub fn preview_file_patches(patches: Vec<FilePatch>) -> Vec<PatchPreview> {
+    patches
+        .into_iter()
+        .map(|patch| {
+            match patch
+                .resolve_path()
+                .and_then(|path| std::fs::read_to_string(path).map_err(anyhow::Error::from))
+            {
+                Ok(original) => PatchPreview::new(patch, Some(&original)),
+                Err(error)
+                    if error
+                        .downcast_ref::<std::io::Error>()
+                        .is_some_and(|error| error.kind() == std::io::ErrorKind::NotFound) =>
+                {
+                    PatchPreview::new(patch, None)
+                }
+                Err(error) => PatchPreview {
+                    patch,
+                    is_new_file: false,
+                    applied: false,
+                    result: Err(format!("{error:#}")),
+                },
+            }
+        })
+        .collect()
+}
+
+pub fn apply_patch_preview(preview: &PatchPreview) -> Result<()> {
+    let (patched, _) = preview
+        .result
+        .as_ref()
+        .map_err(|error| anyhow::anyhow!("patch cannot be applied: {error}"))?;
+    let path = preview.patch.resolve_path()?;
+    if preview.patch.change == Change::Delete {
+        return std::fs::remove_file(path).context("delete file");
+    }
+    if let Some(parent_dir) = path.parent() {
+        std::fs::create_dir_all(parent_dir).context("create parent directories")?;
+    }
+    std::fs::write(path, patched).context("write patched file")
+}
@@ -1,10 +1,23 @@
+use crate::api::{Parameter, Provider};
 use crate::app::actions;
 use crate::app::focus::{Chat as ChatFocus, Config as ConfigFocus, Scope, Tab as TabFocus};
 use crate::app::hotkeys::HotkeyAction;
-use crate::app::state::State;
-use crate::chat::{Conversation, Message};
+use crate::app::state::{State, Ui};
+use crate::chat::{CodeBlock, ContextStrategy, Conversation, Message, Role};
+use crate::config::{
+    get_named_file, get_unused_preset_name, is_valid_name, ModelEntry, ModelField, Snippet,
+    SystemInstructions,
+};
 use anyhow::{Context, Result};
-use ratatui::crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use ratatui::crossterm::event::{
+    self, Event, KeyEvent, KeyEventKind, MouseButton, MouseEvent, MouseEventKind,
+};
+use strum::IntoEnumIterator;
+use tui_textarea::{CursorMove, TextArea};
+
+const MOUSE_SCROLL_LINES: u16 = 3;
+const LARGE_PASTE_CHARS: usize = 20_000;
+const NEW_PRESET_MESSAGE: &str = "You are a helpful assistant.";
 
 pub enum HandleEventResult {
     None,
@@ -15,16 +28,16 @@ pub enum HandleEventResult {
 pub async fn handle(timeout: u64, state: &mut State) -> Result<HandleEventResult> {
     if !event::poll(std::time::Duration::from_millis(timeout)).context("poll terminal events")? {
         return Ok(HandleEventResult::None);
-    };
+    }
     let terminal_event = event::read().context("read terminal event")?;
     match terminal_event {
         Event::Key(key_event) => return handle_keys(key_event, state).await,
         Event::FocusGained => state.add_debug_log("focus gained"),
         Event::FocusLost => state.add_debug_log("focus lost"),
-        Event::Mouse(ev) => state.add_debug_log(format!("mouse {ev:#?}")),
-        Event::Paste(p) => state.add_debug_log(format!("paste {p:#?}")),
+        Event::Mouse(mouse_event) => handle_mouse(mouse_event, state),
+        Event::Paste(text) => handle_paste(&text, state),
         Event::Resize(x, y) => state.add_debug_log(format!("resize {x}x{y}")),
-    };
+    }
     Ok(HandleEventResult::None)
 }
 
@@ -43,15 +56,193 @@ async fn handle_keys(key_event: KeyEvent, state: &mut State) -> Result<HandleEve
         (Scope::Chat(chat_focus), hotkey_action_option) => {
             return handle_chat(hotkey_action_option, state, chat_focus, key_event).await;
         }
-        (Scope::Config(config_focus), Some(hotkey_action)) => {
-            return handle_config(hotkey_action, config_focus, state);
+        (Scope::Config(config_focus), hotkey_action_option) => {
+            return handle_config(hotkey_action_option, config_focus, state, key_event);
         }
+        (Scope::Stats, Some(HotkeyAction::Cancel)) => state.ui.focus.set_tab(TabFocus::Chat),
         (Scope::Debug, Some(hotkey_action)) => handle_debug(hotkey_action, state),
         _ => (),
     }
     Ok(HandleEventResult::None)
 }
 
+fn handle_paste(text: &str, state: &mut State) {
+    // Terminals commonly send carriage returns for newlines when pasting
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    match state.ui.focus.get_scope() {
+        Scope::Chat(ChatFocus::CodeBlockSave) => {
+            let single_line = text.lines().collect::<String>();
+            state.ui.code_block_path_textarea.insert_str(single_line);
+        }
+        Scope::Chat(ChatFocus::Snippets) => {
+            let single_line = text.lines().collect::<Vec<&str>>().join(" ");
+            state.ui.snippet_filter_textarea.insert_str(single_line);
+            state.ui.snippet_selection = 0;
+        }
+        Scope::Chat(ChatFocus::SnippetSave | ChatFocus::PromptSearch) => {
+            let single_line = text.lines().collect::<Vec<&str>>().join(" ");
+            state.ui.rename_textarea.insert_str(single_line);
+            if state.ui.focus.chat == ChatFocus::PromptSearch {
+                search_prompt_history(state, false);
+            }
+        }
+        Scope::Chat(ChatFocus::Messages | ChatFocus::Prompt) => {
+            state.ui.focus.chat = ChatFocus::Prompt;
+            state.ui.prompt_textarea.insert_str(&text);
+            let char_count = text.chars().count();
+            if char_count > LARGE_PASTE_CHARS {
+                state.set_status_bar_warning(format!(
+                    "Pasted a very large text: {char_count} characters in {} lines",
+                    text.lines().count()
+                ));
+            }
+        }
+        _ => state.add_debug_log(format!("Ignored paste of {} bytes", text.len())),
+    }
+}
+
+fn handle_mouse(mouse_event: MouseEvent, state: &mut State) {
+    let (column, row) = (mouse_event.column, mouse_event.row);
+    match mouse_event.kind {
+        MouseEventKind::Down(MouseButton::Left) => handle_mouse_click(column, row, state),
+        MouseEventKind::ScrollUp => handle_mouse_scroll(column, row, true, state),
+        MouseEventKind::ScrollDown => handle_mouse_scroll(column, row, false, state),
+        _ => (),
+    }
+}
+
+fn handle_mouse_click(column: u16, row: u16, state: &mut State) {
+    let position = (column, row).into();
+    let scope = state.ui.focus.get_scope();
+    let clicked_tab = state
+        .ui
+        .mouse_areas
+        .tabs
+        .iter()
+        .find(|(area, _)| area.contains(position))
+        .map(|(_, tab)| *tab);
+    if let Some(tab) = clicked_tab {
+        state.ui.focus.set_tab(tab);
+        return;
+    }
+    match scope {
+        Scope::Chat(ChatFocus::Messages | ChatFocus::Prompt | ChatFocus::Sidebar) => {
+            if let Some(index) = state
+                .ui
+                .mouse_areas
+                .get_sidebar_index(column, row)
+                .filter(|_| state.ui.sidebar_visible)
+            {
+                state.ui.focus.chat = ChatFocus::Sidebar;
+                if index < state.conversations.len() {
+                    state.ui.active_conversation_index = index;
+                    state.ui.message_selection = None;
+                }
+            } else if state.ui.mouse_areas.conversation.contains(position) {
+                state.ui.focus.chat = ChatFocus::Messages;
+            } else if state.ui.mouse_areas.prompt.contains(position) {
+                state.ui.focus.chat = ChatFocus::Prompt;
+            }
+        }
+        Scope::Chat(ChatFocus::New) => {
+            let Some(index) = state.ui.mouse_areas.get_list_index(column, row) else {
+                return;
+            };
+            if index >= state.config.system.instructions.len() {
+                return;
+            }
+            // Clicking the selected item opens it
+            if index == state.ui.system_instruction_selection {
+                start_new_conversation(state);
+            } else {
+                state.ui.system_instruction_selection = index;
+            }
+        }
+        Scope::Chat(ChatFocus::Snippets) => {
+            let Some(index) = state.ui.mouse_areas.get_list_index(column, row) else {
+                return;
+            };
+            if index >= state.get_filtered_snippets().len() {
+                return;
+            }
+            if index == state.ui.snippet_selection {
+                insert_selected_snippet(state);
+            } else {
+                state.ui.snippet_selection = index;
+            }
+        }
+        Scope::Chat(ChatFocus::History) => {
+            let Some(index) = state.ui.mouse_areas.get_list_index(column, row) else {
+                return;
+            };
+            if index >= state.conversations.len() {
+                return;
+            }
+            if index == state.ui.active_conversation_index {
+                handle_chat_history(HotkeyAction::Select, state);
+            } else {
+                state.ui.active_conversation_index = index;
+                state.ui.message_selection = None;
+            }
+        }
+        _ => (),
+    }
+}
+
+fn handle_mouse_scroll(column: u16, row: u16, up: bool, state: &mut State) {
+    let position = (column, row).into();
+    let scope = state.ui.focus.get_scope();
+    match scope {
+        Scope::Chat(ChatFocus::Messages | ChatFocus::Prompt | ChatFocus::Sidebar)
+            if state.ui.mouse_areas.conversation.contains(position) =>
+        {
+            state.ui.conversation_scroll = if up {
+                state
+                    .ui
+                    .conversation_scroll
+                    .saturating_sub(MOUSE_SCROLL_LINES)
+            } else {
+                state
+                    .ui
+                    .conversation_scroll
+                    .saturating_add(MOUSE_SCROLL_LINES)
+            };
+        }
+        Scope::Chat(ChatFocus::Messages | ChatFocus::Prompt | ChatFocus::Sidebar)
+            if state.ui.sidebar_visible && state.ui.mouse_areas.sidebar.contains(position) =>
+        {
+            let hotkey_action = if up {
+                HotkeyAction::SelectionUp
+            } else {
+                HotkeyAction::SelectionDown
+            };
+            handle_chat_history(hotkey_action, state);
+        }
+        Scope::Chat(ChatFocus::History) => {
+            let hotkey_action = if up {
+                HotkeyAction::SelectionUp
+            } else {
+                HotkeyAction::SelectionDown
+            };
+            handle_chat_history(hotkey_action, state);
+        }
+        Scope::Debug if state.ui.mouse_areas.debug.contains(position) => {
+            state.ui.debug_logs_scroll = if up {
+                state
+                    .ui
+                    .debug_logs_scroll
+                    .saturating_sub(MOUSE_SCROLL_LINES)
+            } else {
+                state
+                    .ui
+                    .debug_logs_scroll
+                    .saturating_add(MOUSE_SCROLL_LINES)
+            };
+        }
+        _ => (),
+    }
+}
+
 async fn handle_chat(
     hotkey_action_option: Option<HotkeyAction>,
     state: &mut State,
@@ -59,6 +250,44 @@ async fn handle_chat(
     key_event: KeyEvent,
 ) -> Result<HandleEventResult> {
     match (chat_focus, hotkey_action_option) {
+        // The code block picker captures all hotkeys
+        (ChatFocus::CodeBlocks, Some(hotkey_action)) => {
+            return handle_code_blocks(hotkey_action, state).context("handle code blocks");
+        }
+        (ChatFocus::CodeBlockSave, _) => {
+            handle_code_block_save(hotkey_action_option, key_event, state)
+                .context("handle code block save")?;
+        }
+        (ChatFocus::Patches, Some(hotkey_action)) => {
+            handle_patches(hotkey_action, state);
+        }
+        (ChatFocus::Parameters, Some(hotkey_action)) => {
+            handle_parameters(hotkey_action, state).context("handle parameters")?;
+        }
+        (ChatFocus::Rename, _) => {
+            handle_rename(hotkey_action_option, key_event, state).context("handle rename")?;
+        }
+        // The new conversation screen captures all hotkeys to manage the presets
+        (ChatFocus::New, Some(hotkey_action)) => {
+            return handle_new_conversation(hotkey_action, state)
+                .context("handle new conversation");
+        }
+        (ChatFocus::PresetRename, _) => {
+            handle_preset_rename(hotkey_action_option, key_event, state)
+                .context("handle preset rename")?;
+        }
+        // The snippet finder captures all keys to search the snippets
+        (ChatFocus::Snippets, _) => {
+            return handle_snippets(hotkey_action_option, key_event, state)
+                .context("handle snippets");
+        }
+        (ChatFocus::SnippetSave, _) => {
+            handle_snippet_save(hotkey_action_option, key_event, state)
+                .context("handle snippet save")?;
+        }
+        (ChatFocus::PromptSearch, _) => {
+            handle_prompt_search(hotkey_action_option, key_event, state);
+        }
         // Focus-independent hotkeys
         (_, Some(HotkeyAction::Confirm)) => {
             let text = state.ui.prompt_textarea.lines().join("\n");
@@ -66,13 +295,13 @@ async fn handle_chat(
                 state.set_status_bar_text("Cannot send empty message.");
                 return Ok(HandleEventResult::None);
             }
-            let message = Message::new_user_message(text);
-            state.get_active_conversation_mut()?.add_message(message);
+            state.record_prompt(&text);
+            let message = Message::new_user_message(state.expand_template(&text));
             state.ui.focus.chat = ChatFocus::Messages;
-            actions::do_prompt(state).await?;
+            actions::do_prompt(state, message).await?;
             state
-                .save_conversations_to_disk()
-                .context("save conversations")?;
+                .save_active_conversation()
+                .context("save conversation")?;
         }
         (_, Some(HotkeyAction::Edit)) => {
             let initial_text = state.ui.prompt_textarea.lines().join("\n");
@@ -87,13 +316,30 @@ async fn handle_chat(
             state.ui.focus.chat = ChatFocus::New;
         }
         (_, Some(HotkeyAction::Open)) => {
-            state.ui.focus.chat = ChatFocus::History;
+            state.ui.focus.chat = if state.ui.sidebar_visible {
+                ChatFocus::Sidebar
+            } else {
+                ChatFocus::History
+            };
         }
-        // Scope-dependent hotkeys
-        (ChatFocus::New, Some(hotkey_action)) => {
-            handle_new_conversation(hotkey_action, state);
+        (_, Some(HotkeyAction::Rename)) => open_rename(state)?,
+        (_, Some(HotkeyAction::Parameters)) => state.ui.focus.chat = ChatFocus::Parameters,
+        (_, Some(HotkeyAction::CycleContext)) => cycle_context_strategy(state)?,
+        (_, Some(HotkeyAction::ToggleMetadata)) => {
+            state.ui.metadata_visible = !state.ui.metadata_visible;
         }
-        (ChatFocus::History, Some(hotkey_action)) => {
+        (_, Some(HotkeyAction::ToggleSidebar)) => {
+            state.ui.sidebar_visible = !state.ui.sidebar_visible;
+            state.ui.focus.chat = if state.ui.sidebar_visible {
+                ChatFocus::Sidebar
+            } else {
+                ChatFocus::Messages
+            };
+        }
+        (_, Some(HotkeyAction::CodeBlocks)) => open_code_blocks(state),
+        (_, Some(HotkeyAction::Patch)) => open_patches(state),
+        // Scope-dependent hotkeys
+        (ChatFocus::History | ChatFocus::Sidebar, Some(hotkey_action)) => {
             handle_chat_history(hotkey_action, state);
         }
         (ChatFocus::Messages, Some(hotkey_action)) => {
@@ -108,6 +354,65 @@ async fn handle_chat(
     Ok(HandleEventResult::None)
 }
 
+fn cycle_context_strategy(state: &mut State) -> Result<()> {
+    let context_config = &state.config.context;
+    let next_strategy = match state.get_active_conversation()?.context_strategy {
+        ContextStrategy::All => ContextStrategy::LastMessages {
+            count: context_config.last_messages,
+        },
+        ContextStrategy::LastMessages { .. } => ContextStrategy::TokenBudget {
+            tokens: context_config.token_budget,
+        },
+        ContextStrategy::TokenBudget { .. } => ContextStrategy::Summarize {
+            keep: context_config.summarize_keep,
+        },
+        ContextStrategy::Summarize { .. } => ContextStrategy::All,
+    };
+    state.get_active_conversation_mut()?.context_strategy = next_strategy;
+    state
+        .save_active_conversation()
+        .context("save conversation")?;
+    state.set_status_bar_text(format!("Context strategy: {next_strategy}"));
+    Ok(())
+}
+
+fn open_rename(state: &mut State) -> Result<()> {
+    let title = state.get_active_conversation()?.title.clone();
+    state.ui.rename_textarea = TextArea::new(vec![title.unwrap_or_default()]);
+    state.ui.rename_textarea.move_cursor(CursorMove::End);
+    state.ui.focus.chat = ChatFocus::Rename;
+    Ok(())
+}
+
+fn open_code_blocks(state: &mut State) {
+    if state
+        .get_selected_message()
+        .is_ok_and(|message| !message.code_blocks().is_empty())
+    {
+        state.ui.code_block_selection = 0;
+        state.ui.focus.chat = ChatFocus::CodeBlocks;
+    } else {
+        state.set_status_bar_text("No code blocks in selected message.");
+    }
+}
+
+fn open_patches(state: &mut State) {
+    let patches = state
+        .get_selected_message()
+        .ok()
+        .filter(|message| matches!(message.role, Role::Assistant(_)))
+        .map(Message::file_patches)
+        .unwrap_or_default();
+    if patches.is_empty() {
+        state.set_status_bar_text("No diffs or files in selected assistant message.");
+    } else {
+        state.ui.patch_previews = actions::preview_file_patches(patches);
+        state.ui.patch_selection = 0;
+        state.ui.patch_preview_scroll = 0;
+        state.ui.focus.chat = ChatFocus::Patches;
+    }
+}
+
 fn handle_conversation(hotkey_action: HotkeyAction, state: &mut State) -> Result<()> {
     match hotkey_action {
         HotkeyAction::Select => {
@@ -131,6 +436,22 @@ fn handle_conversation(hotkey_action: HotkeyAction, state: &mut State) -> Result
         HotkeyAction::SelectionEnd => {
             state.ui.conversation_scroll = u16::MAX;
         }
+        HotkeyAction::PreviousMessage => {
+            state.ui.message_selection = state
+                .get_selected_message_index()
+                .map(|index| index.saturating_sub(1));
+        }
+        HotkeyAction::NextMessage => {
+            let message_count = state.get_active_conversation()?.messages.len();
+            // Selecting past the last message follows new messages
+            state.ui.message_selection = state
+                .get_selected_message_index()
+                .map(|index| index.saturating_add(1))
+                .filter(|index| index.saturating_add(1) < message_count);
+        }
+        HotkeyAction::ToggleIncluded | HotkeyAction::TogglePinned => {
+            toggle_message_flag(state, hotkey_action == HotkeyAction::TogglePinned)?;
+        }
         HotkeyAction::Copy => {
             let text = state
                 .get_active_conversation()
@@ -142,10 +463,88 @@ fn handle_conversation(hotkey_action: HotkeyAction, state: &mut State) -> Result
             state.set_status_bar_text("Copied conversation to clipboard");
         }
         _ => (),
-    };
+    }
     Ok(())
 }
 
+fn handle_parameters(hotkey_action: HotkeyAction, state: &mut State) -> Result<()> {
+    let parameters: Vec<Parameter> = Parameter::iter().collect();
+    let parameter = *parameters
+        .get(state.ui.parameter_selection)
+        .context("parameter selection out of bounds")?;
+    match hotkey_action {
+        HotkeyAction::Cancel | HotkeyAction::Parameters => {
+            state.ui.focus.chat = ChatFocus::Messages;
+        }
+        HotkeyAction::SelectionUp => {
+            state.ui.parameter_selection = state.ui.parameter_selection.saturating_sub(1);
+        }
+        HotkeyAction::SelectionDown => {
+            state.ui.parameter_selection = state
+                .ui
+                .parameter_selection
+                .saturating_add(1)
+                .min(parameters.len().saturating_sub(1));
+        }
+        HotkeyAction::Increment | HotkeyAction::Decrement | HotkeyAction::Clear => {
+            if !parameter.is_supported(state.config.provider) {
+                state.set_status_bar_text(format!(
+                    "{parameter} is not supported by {}.",
+                    state.config.provider
+                ));
+                return Ok(());
+            }
+            if !crate::api::get_active_model_capabilities(state).supports(parameter) {
+                state.set_status_bar_text(format!("{parameter} is not supported by the model."));
+                return Ok(());
+            }
+            let defaults = crate::api::get_active_model_parameters(state);
+            let provider = state.config.provider;
+            let overrides = &mut state.get_active_conversation_mut()?.parameters;
+            match hotkey_action {
+                HotkeyAction::Clear => overrides.clear(parameter),
+                _ => overrides.step(
+                    parameter,
+                    provider,
+                    &defaults,
+                    hotkey_action == HotkeyAction::Increment,
+                ),
+            }
+            state
+                .save_active_conversation()
+                .context("save conversation")?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn toggle_message_flag(state: &mut State, pinned: bool) -> Result<()> {
+    let Ok(message) = state.get_selected_message_mut() else {
+        state.set_status_bar_text("No message selected.");
+        return Ok(());
+    };
+    let feedback = if pinned {
+        message.pinned = !message.pinned;
+        if message.pinned {
+            "Message pinned to the context."
+        } else {
+            "Message unpinned from the context."
+        }
+    } else {
+        message.included = !message.included;
+        if message.included {
+            "Message included in the context."
+        } else {
+            "Message excluded from the context."
+        }
+    };
+    state.set_status_bar_text(feedback);
+    state
+        .save_active_conversation()
+        .context("save conversation")
+}
+
 fn handle_conversation_prompt(
     hotkey_action_option: Option<HotkeyAction>,
     key_event: KeyEvent,
@@ -171,6 +570,45 @@ fn handle_conversation_prompt(
             state.add_debug_log("Copied last message to clipboard");
             state.set_status_bar_text("Copied last message to clipboard");
         }
+        Some(HotkeyAction::SelectionUp) if state.ui.prompt_textarea.cursor().0 == 0 => {
+            let before = state
+                .ui
+                .prompt_history_selection
+                .unwrap_or(state.prompt_history.entries.len());
+            match before.checked_sub(1) {
+                Some(index) => recall_prompt(state, Some(index)),
+                None => state.set_status_bar_text("No older prompts in history."),
+            }
+        }
+        Some(HotkeyAction::SelectionDown)
+            if state.ui.prompt_history_selection.is_some()
+                && state.ui.prompt_textarea.cursor().0.saturating_add(1)
+                    >= state.ui.prompt_textarea.lines().len() =>
+        {
+            let newer = state
+                .ui
+                .prompt_history_selection
+                .map(|index| index.saturating_add(1))
+                .filter(|index| *index < state.prompt_history.entries.len());
+            recall_prompt(state, newer);
+        }
+        Some(HotkeyAction::SearchHistory) => {
+            state.ui.rename_textarea = TextArea::default();
+            state.ui.focus.chat = ChatFocus::PromptSearch;
+        }
+        Some(HotkeyAction::Snippets) => {
+            state.ui.snippet_filter_textarea = TextArea::default();
+            state.ui.snippet_selection = 0;
+            state.ui.focus.chat = ChatFocus::Snippets;
+        }
+        Some(HotkeyAction::Save) => {
+            if state.ui.prompt_textarea.is_empty() {
+                state.set_status_bar_text("Cannot save empty prompt as a snippet.");
+                return Ok(());
+            }
+            state.ui.rename_textarea = TextArea::default();
+            state.ui.focus.chat = ChatFocus::SnippetSave;
+        }
         _ => {
             state.ui.prompt_textarea.input(key_event);
         }
@@ -178,29 +616,453 @@ fn handle_conversation_prompt(
     Ok(())
 }
 
-fn handle_new_conversation(hotkey_action: HotkeyAction, state: &mut State) {
-    let max_selection = state.config.system.instructions.len().saturating_sub(1);
+/// Replace the prompt with an entry of the prompt history, or with the draft if `None`.
+fn recall_prompt(state: &mut State, selection: Option<usize>) {
+    match (state.ui.prompt_history_selection, selection) {
+        // The prompt is already the draft
+        (None, None) => return,
+        (None, Some(_)) => {
+            state.ui.prompt_draft = state.ui.prompt_textarea.lines().join("\n");
+        }
+        (Some(_), _) => (),
+    }
+    let text = match selection {
+        Some(index) => state
+            .prompt_history
+            .entries
+            .get(index)
+            .cloned()
+            .unwrap_or_default(),
+        None => std::mem::take(&mut state.ui.prompt_draft),
+    };
+    state.ui.prompt_history_selection = selection;
+    state.ui.set_prompt_text(&text);
+}
+
+/// Recall the newest prompt matching the search, or the next older match.
+fn search_prompt_history(state: &mut State, older: bool) {
+    let query = state.ui.rename_textarea.lines().join(" ");
+    let before = match state.ui.prompt_history_selection {
+        Some(index) if older => index,
+        _ => state.prompt_history.entries.len(),
+    };
+    match state.prompt_history.search(&query, before) {
+        Some(index) => recall_prompt(state, Some(index)),
+        None => state.set_status_bar_text(format!("No older prompts matching: {query}")),
+    }
+}
+
+fn handle_prompt_search(
+    hotkey_action_option: Option<HotkeyAction>,
+    key_event: KeyEvent,
+    state: &mut State,
+) {
+    match hotkey_action_option {
+        Some(HotkeyAction::Cancel) => {
+            recall_prompt(state, None);
+            state.ui.focus.chat = ChatFocus::Prompt;
+        }
+        Some(HotkeyAction::Select) => state.ui.focus.chat = ChatFocus::Prompt,
+        Some(HotkeyAction::SearchHistory) => search_prompt_history(state, true),
+        _ => {
+            if state.ui.rename_textarea.input(key_event) {
+                search_prompt_history(state, false);
+            }
+        }
+    }
+}
+
+fn insert_selected_snippet(state: &mut State) {
+    let Some(text) = state
+        .get_filtered_snippets()
+        .get(state.ui.snippet_selection)
+        .map(|snippet| snippet.text.trim_end().to_owned())
+    else {
+        return;
+    };
+    state.ui.prompt_textarea.insert_str(text);
+    state.ui.focus.chat = ChatFocus::Prompt;
+}
+
+fn handle_snippets(
+    hotkey_action_option: Option<HotkeyAction>,
+    key_event: KeyEvent,
+    state: &mut State,
+) -> Result<HandleEventResult> {
+    let selected_snippet = state
+        .get_filtered_snippets()
+        .get(state.ui.snippet_selection)
+        .map(|snippet| (*snippet).clone());
+    let max_selection = state.get_filtered_snippets().len().saturating_sub(1);
+    let delete_confirmation = state.ui.snippet_delete_confirmation.take();
+    match hotkey_action_option {
+        Some(HotkeyAction::Cancel) => state.ui.focus.chat = ChatFocus::Prompt,
+        Some(HotkeyAction::Select) => insert_selected_snippet(state),
+        Some(HotkeyAction::Edit) => {
+            let Some(snippet) = selected_snippet else {
+                return Ok(HandleEventResult::None);
+            };
+            actions::edit_file_in_editor(state, &snippet.file_path(&state.paths.snippets_dir))?;
+            state.reload_snippets()?;
+            state.set_status_bar_text(format!("Edited snippet {}.", snippet.name));
+            return Ok(HandleEventResult::Redraw);
+        }
+        Some(HotkeyAction::Clear) => {
+            let Some(snippet) = selected_snippet else {
+                return Ok(HandleEventResult::None);
+            };
+            if delete_confirmation.as_ref() != Some(&snippet.name) {
+                state.set_status_bar_warning(format!(
+                    "Clear again to delete snippet {}.",
+                    snippet.name
+                ));
+                state.ui.snippet_delete_confirmation = Some(snippet.name);
+                return Ok(HandleEventResult::None);
+            }
+            std::fs::remove_file(snippet.file_path(&state.paths.snippets_dir))
+                .context("delete snippet file")?;
+            state.reload_snippets()?;
+            state.ui.snippet_selection = state
+                .ui
+                .snippet_selection
+                .min(state.get_filtered_snippets().len().saturating_sub(1));
+            state.set_status_bar_text(format!("Deleted snippet {}.", snippet.name));
+        }
+        Some(HotkeyAction::SelectionUp) => {
+            state.ui.snippet_selection = state.ui.snippet_selection.saturating_sub(1);
+        }
+        Some(HotkeyAction::SelectionDown) => {
+            state.ui.snippet_selection = state
+                .ui
+                .snippet_selection
+                .saturating_add(1)
+                .min(max_selection);
+        }
+        Some(HotkeyAction::SelectionStart) => {
+            state.ui.snippet_selection = 0;
+        }
+        Some(HotkeyAction::SelectionEnd) => {
+            state.ui.snippet_selection = max_selection;
+        }
+        _ => {
+            if state.ui.snippet_filter_textarea.input(key_event) {
+                state.ui.snippet_selection = 0;
+            }
+        }
+    }
+    Ok(HandleEventResult::None)
+}
+
+fn handle_snippet_save(
+    hotkey_action_option: Option<HotkeyAction>,
+    key_event: KeyEvent,
+    state: &mut State,
+) -> Result<()> {
+    match hotkey_action_option {
+        Some(HotkeyAction::Cancel) => state.ui.focus.chat = ChatFocus::Prompt,
+        Some(HotkeyAction::Select) => {
+            let name = state.ui.rename_textarea.lines().join(" ").trim().to_owned();
+            if !is_valid_name(&name) {
+                state.set_status_bar_warning(format!("Invalid snippet name: {name}"));
+                return Ok(());
+            }
+            if get_named_file(&state.paths.snippets_dir, &name).exists() {
+                state.set_status_bar_warning(format!("Snippet {name} already exists."));
+                return Ok(());
+            }
+            let snippet = Snippet {
+                name,
+                text: state.ui.prompt_textarea.lines().join("\n"),
+            };
+            snippet.to_file(&state.paths.snippets_dir)?;
+            state.reload_snippets()?;
+            state.set_status_bar_text(format!("Saved prompt as snippet {}.", snippet.name));
+            state.ui.focus.chat = ChatFocus::Prompt;
+        }
+        _ => {
+            state.ui.rename_textarea.input(key_event);
+        }
+    }
+    Ok(())
+}
+
+fn handle_rename(
+    hotkey_action_option: Option<HotkeyAction>,
+    key_event: KeyEvent,
+    state: &mut State,
+) -> Result<()> {
+    match hotkey_action_option {
+        Some(HotkeyAction::Cancel) => {
+            state.ui.focus.chat = ChatFocus::Messages;
+        }
+        Some(HotkeyAction::Select) => {
+            let title_text = state.ui.rename_textarea.lines().join(" ");
+            let title = Some(title_text.trim().to_owned()).filter(|title| !title.is_empty());
+            let generate = title.is_none() && !state.get_active_conversation()?.is_empty();
+            state.get_active_conversation_mut()?.title = title;
+            state
+                .save_active_conversation()
+                .context("save conversation")?;
+            if !generate {
+                state.set_status_bar_text("Renamed conversation.");
+            } else if state
+                .start_title_generation()
+                .context("start title generation")?
+            {
+                state.set_status_bar_text("Generating conversation title...");
+            } else {
+                state.set_status_bar_warning("Spending limit reached, not generating a title.");
+            }
+            state.ui.focus.chat = ChatFocus::Messages;
+        }
+        _ => {
+            state.ui.rename_textarea.input(key_event);
+        }
+    }
+    Ok(())
+}
+
+fn get_selected_code_block(state: &State) -> Result<CodeBlock> {
+    state
+        .get_selected_message()?
+        .code_blocks()
+        .get(state.ui.code_block_selection)
+        .cloned()
+        .context("code block selection out of bounds")
+}
+
+fn get_code_block_extension<'a>(state: &'a State, code_block: &CodeBlock) -> &'a str {
+    code_block
+        .language
+        .as_deref()
+        .and_then(|language| state.highlighter.file_extension(language))
+        .unwrap_or("txt")
+}
+
+fn handle_code_blocks(hotkey_action: HotkeyAction, state: &mut State) -> Result<HandleEventResult> {
+    let max_selection = state
+        .get_selected_message()?
+        .code_blocks()
+        .len()
+        .saturating_sub(1);
     match hotkey_action {
         HotkeyAction::Cancel => state.ui.focus.chat = ChatFocus::Messages,
-        HotkeyAction::Select => {
-            if let Some(system_instructions) = state
-                .config
-                .system
-                .instructions
-                .get(state.ui.system_instruction_selection)
-            {
-                if state
-                    .conversations
-                    .first()
-                    .is_some_and(Conversation::is_empty)
-                {
-                    state.conversations.remove(0);
+        HotkeyAction::Select | HotkeyAction::Copy => {
+            let code_block = get_selected_code_block(state)?;
+            actions::export_to_clipboard(state, &code_block.code)
+                .context("export code block to clipboard")?;
+            state.add_debug_log("Copied code block to clipboard");
+            state.set_status_bar_text("Copied code block to clipboard");
+        }
+        HotkeyAction::Save => {
+            let code_block = get_selected_code_block(state)?;
+            let suggested_path = format!(
+                "snippet_{}.{}",
+                state.ui.code_block_selection.saturating_add(1),
+                get_code_block_extension(state, &code_block)
+            );
+            state.ui.code_block_path_textarea = TextArea::new(vec![suggested_path]);
+            state
+                .ui
+                .code_block_path_textarea
+                .move_cursor(CursorMove::End);
+            state.ui.focus.chat = ChatFocus::CodeBlockSave;
+        }
+        HotkeyAction::Edit => {
+            let code_block = get_selected_code_block(state)?;
+            let extension = get_code_block_extension(state, &code_block).to_owned();
+            actions::open_text_in_editor(state, &code_block.code, &extension)
+                .context("open code block in editor")?;
+            return Ok(HandleEventResult::Redraw);
+        }
+        HotkeyAction::SelectionUp => {
+            state.ui.code_block_selection = state.ui.code_block_selection.saturating_sub(1);
+        }
+        HotkeyAction::SelectionDown => {
+            state.ui.code_block_selection = state
+                .ui
+                .code_block_selection
+                .saturating_add(1)
+                .min(max_selection);
+        }
+        HotkeyAction::SelectionStart => {
+            state.ui.code_block_selection = 0;
+        }
+        HotkeyAction::SelectionEnd => {
+            state.ui.code_block_selection = max_selection;
+        }
+        _ => (),
+    }
+    Ok(HandleEventResult::None)
+}
+
+fn handle_code_block_save(
+    hotkey_action_option: Option<HotkeyAction>,
+    key_event: KeyEvent,
+    state: &mut State,
+) -> Result<()> {
+    let overwrite = match hotkey_action_option {
+        Some(HotkeyAction::Cancel) => {
+            state.ui.focus.chat = ChatFocus::CodeBlocks;
+            return Ok(());
+        }
+        Some(HotkeyAction::Select) => false,
+        Some(HotkeyAction::Save) => true,
+        _ => {
+            state.ui.code_block_path_textarea.input(key_event);
+            return Ok(());
+        }
+    };
+    let path_text = state.ui.code_block_path_textarea.lines().join("");
+    let file_path = std::path::PathBuf::from(path_text.trim());
+    if file_path.exists() && !overwrite {
+        state.set_status_bar_text(format!(
+            "File already exists: {} (save again to overwrite)",
+            file_path.display()
+        ));
+        return Ok(());
+    }
+    let code_block = get_selected_code_block(state)?;
+    match std::fs::write(&file_path, &code_block.code) {
+        Ok(()) => {
+            let feedback = format!("Saved code block to: {}", file_path.display());
+            state.add_debug_log(&feedback);
+            state.set_status_bar_text(feedback);
+            state.ui.focus.chat = ChatFocus::CodeBlocks;
+        }
+        Err(error) => {
+            state.set_status_bar_text(format!("Failed to save code block: {error}"));
+            state.add_debug_log(format!("{error:?}"));
+        }
+    }
+    Ok(())
+}
+
+fn handle_patches(hotkey_action: HotkeyAction, state: &mut State) {
+    let max_selection = state.ui.patch_previews.len().saturating_sub(1);
+    let previous_selection = state.ui.patch_selection;
+    match hotkey_action {
+        HotkeyAction::Cancel => {
+            state.ui.patch_previews.clear();
+            state.ui.focus.chat = ChatFocus::Messages;
+        }
+        HotkeyAction::Confirm => {
+            let Some(preview) = state.ui.patch_previews.get(state.ui.patch_selection) else {
+                return;
+            };
+            let path = preview.patch.path.display().to_string();
+            if preview.applied {
+                state.set_status_bar_text(format!("Already applied to: {path}"));
+                return;
+            }
+            match actions::apply_patch_preview(preview) {
+                Ok(()) => {
+                    if let Some(preview) = state.ui.patch_previews.get_mut(state.ui.patch_selection)
+                    {
+                        preview.applied = true;
+                    }
+                    state.add_debug_log(format!("Applied patch to: {path}"));
+                    state.set_status_bar_text(format!("Applied patch to: {path}"));
                 }
-                let new_conversation = Conversation::new(system_instructions.message.clone());
-                state.conversations.insert(0, new_conversation);
-                state.ui.active_conversation_index = 0;
-                state.ui.focus.chat = ChatFocus::Prompt;
+                Err(error) => {
+                    state.set_status_bar_text(format!("Failed to apply patch to: {path}"));
+                    state.add_debug_log(format!("{error:?}"));
+                }
+            }
+        }
+        HotkeyAction::SelectionUp => {
+            state.ui.patch_selection = state.ui.patch_selection.saturating_sub(1);
+        }
+        HotkeyAction::SelectionDown => {
+            state.ui.patch_selection = state
+                .ui
+                .patch_selection
+                .saturating_add(1)
+                .min(max_selection);
+        }
+        HotkeyAction::SelectionStart => {
+            state.ui.patch_selection = 0;
+        }
+        HotkeyAction::SelectionEnd => {
+            state.ui.patch_selection = max_selection;
+        }
+        HotkeyAction::ScrollUp => {
+            state.ui.patch_preview_scroll = state.ui.patch_preview_scroll.saturating_sub(10);
+        }
+        HotkeyAction::ScrollDown => {
+            state.ui.patch_preview_scroll = state.ui.patch_preview_scroll.saturating_add(10);
+        }
+        _ => (),
+    }
+    if state.ui.patch_selection != previous_selection {
+        state.ui.patch_preview_scroll = 0;
+    }
+}
+
+fn handle_new_conversation(
+    hotkey_action: HotkeyAction,
+    state: &mut State,
+) -> Result<HandleEventResult> {
+    let max_selection = state.config.system.instructions.len().saturating_sub(1);
+    let selected_preset = state
+        .config
+        .system
+        .instructions
+        .get(state.ui.system_instruction_selection)
+        .cloned();
+    let delete_confirmation = state.ui.preset_delete_confirmation.take();
+    match hotkey_action {
+        HotkeyAction::Cancel => state.ui.focus.chat = ChatFocus::Messages,
+        HotkeyAction::Select => start_new_conversation(state),
+        HotkeyAction::New => {
+            let preset = SystemInstructions {
+                name: get_unused_preset_name(&state.config.system.instructions, "New preset"),
+                message: NEW_PRESET_MESSAGE.to_owned(),
             };
+            preset.to_file(&state.paths.system_dir)?;
+            actions::edit_file_in_editor(state, &preset.file_path(&state.paths.system_dir))?;
+            reload_presets_and_select(state, &preset.name)?;
+            state.set_status_bar_text(format!("Created preset {}.", preset.name));
+            return Ok(HandleEventResult::Redraw);
+        }
+        HotkeyAction::Copy => {
+            let Some(selected_preset) = selected_preset else {
+                return Ok(HandleEventResult::None);
+            };
+            let preset = SystemInstructions {
+                name: get_unused_preset_name(
+                    &state.config.system.instructions,
+                    &format!("{} copy", selected_preset.name),
+                ),
+                message: selected_preset.message,
+            };
+            preset.to_file(&state.paths.system_dir)?;
+            reload_presets_and_select(state, &preset.name)?;
+            state.set_status_bar_text(format!("Duplicated preset as {}.", preset.name));
+        }
+        HotkeyAction::Rename => {
+            let Some(selected_preset) = selected_preset else {
+                return Ok(HandleEventResult::None);
+            };
+            state.ui.rename_textarea = TextArea::new(vec![selected_preset.name]);
+            state.ui.rename_textarea.move_cursor(CursorMove::End);
+            state.ui.focus.chat = ChatFocus::PresetRename;
+        }
+        HotkeyAction::Edit => {
+            let Some(selected_preset) = selected_preset else {
+                return Ok(HandleEventResult::None);
+            };
+            let preset_file = selected_preset.file_path(&state.paths.system_dir);
+            actions::edit_file_in_editor(state, &preset_file)?;
+            reload_presets_and_select(state, &selected_preset.name)?;
+            state.set_status_bar_text(format!("Edited preset {}.", selected_preset.name));
+            return Ok(HandleEventResult::Redraw);
+        }
+        HotkeyAction::Clear => {
+            let Some(selected_preset) = selected_preset else {
+                return Ok(HandleEventResult::None);
+            };
+            delete_preset(state, selected_preset, delete_confirmation.as_deref())?;
         }
         HotkeyAction::SelectionDown => {
             state.ui.system_instruction_selection = state
@@ -232,10 +1094,122 @@ fn handle_new_conversation(hotkey_action: HotkeyAction, state: &mut State) {
         }
         _ => (),
     }
+    Ok(HandleEventResult::None)
+}
+
+/// Delete the preset if clear was pressed on it before, otherwise ask to press clear again.
+fn delete_preset(
+    state: &mut State,
+    preset: SystemInstructions,
+    delete_confirmation: Option<&str>,
+) -> Result<()> {
+    if state.config.system.instructions.len() <= 1 {
+        state.set_status_bar_warning("Cannot delete the last preset.");
+        return Ok(());
+    }
+    if delete_confirmation != Some(preset.name.as_str()) {
+        state.set_status_bar_warning(format!("Clear again to delete preset {}.", preset.name));
+        state.ui.preset_delete_confirmation = Some(preset.name);
+        return Ok(());
+    }
+    std::fs::remove_file(preset.file_path(&state.paths.system_dir))
+        .context("delete preset file")?;
+    state.reload_system_presets()?;
+    state.set_status_bar_text(format!("Deleted preset {}.", preset.name));
+    Ok(())
+}
+
+fn start_new_conversation(state: &mut State) {
+    let Some(system_instructions) = state
+        .config
+        .system
+        .instructions
+        .get(state.ui.system_instruction_selection)
+        .map(|preset| preset.message.clone())
+    else {
+        return;
+    };
+    if let Err(error) = state.save_unsaved_draft() {
+        state.add_debug_log(format!("Failed to save draft: {error:?}"));
+    }
+    let mut new_conversation = Conversation::new(state.expand_template(&system_instructions));
+    // Replace an empty first conversation, keeping its draft
+    if state.conversations.get(0).is_ok_and(Conversation::is_empty) {
+        match state.conversations.remove(0) {
+            Ok(removed) => new_conversation.draft = removed.draft,
+            Err(error) => state.add_debug_log(format!("Failed to remove conversation: {error:?}")),
+        }
+    }
+    state.ui.set_prompt_text(&new_conversation.draft);
+    state.conversations.insert(0, new_conversation);
+    state.ui.active_conversation_index = 0;
+    state.ui.prompt_conversation_index = 0;
+    state.ui.prompt_history_selection = None;
+    state.ui.focus.chat = ChatFocus::Prompt;
+}
+
+fn reload_presets_and_select(state: &mut State, name: &str) -> Result<()> {
+    state.reload_system_presets()?;
+    if let Some(index) = state
+        .config
+        .system
+        .instructions
+        .iter()
+        .position(|preset| preset.name == name)
+    {
+        state.ui.system_instruction_selection = index;
+    }
+    Ok(())
+}
+
+fn handle_preset_rename(
+    hotkey_action_option: Option<HotkeyAction>,
+    key_event: KeyEvent,
+    state: &mut State,
+) -> Result<()> {
+    match hotkey_action_option {
+        Some(HotkeyAction::Cancel) => state.ui.focus.chat = ChatFocus::New,
+        Some(HotkeyAction::Select) => {
+            let name = state.ui.rename_textarea.lines().join(" ").trim().to_owned();
+            let Some(preset) = state
+                .config
+                .system
+                .instructions
+                .get(state.ui.system_instruction_selection)
+                .cloned()
+            else {
+                state.ui.focus.chat = ChatFocus::New;
+                return Ok(());
+            };
+            if name == preset.name {
+                state.ui.focus.chat = ChatFocus::New;
+                return Ok(());
+            }
+            if !is_valid_name(&name) {
+                state.set_status_bar_warning(format!("Invalid preset name: {name}"));
+                return Ok(());
+            }
+            let renamed_file = get_named_file(&state.paths.system_dir, &name);
+            if renamed_file.exists() {
+                state.set_status_bar_warning(format!("Preset {name} already exists."));
+                return Ok(());
+            }
+            std::fs::rename(preset.file_path(&state.paths.system_dir), renamed_file)
+                .context("rename preset file")?;
+            reload_presets_and_select(state, &name)?;
+            state.set_status_bar_text(format!("Renamed preset {} to {name}.", preset.name));
+            state.ui.focus.chat = ChatFocus::New;
+        }
+        _ => {
+            state.ui.rename_textarea.input(key_event);
+        }
+    }
+    Ok(())
 }
 
 fn handle_chat_history(hotkey_action: HotkeyAction, state: &mut State) {
     let max_selection = state.conversations.len().saturating_sub(1);
+    state.ui.message_selection = None;
     match hotkey_action {
         HotkeyAction::Cancel | HotkeyAction::Select => {
             state.ui.focus.chat = ChatFocus::Messages;
@@ -269,16 +1243,43 @@ fn handle_chat_history(hotkey_action: HotkeyAction, state: &mut State) {
                 .min(max_selection);
         }
         _ => (),
-    };
+    }
 }
 
 fn handle_config(
-    hotkey_action: HotkeyAction,
+    hotkey_action_option: Option<HotkeyAction>,
     config_focus: ConfigFocus,
     state: &mut State,
+    key_event: KeyEvent,
 ) -> Result<HandleEventResult> {
-    match (hotkey_action, config_focus) {
-        (HotkeyAction::Cancel, _) => state.ui.focus.set_tab(TabFocus::Chat),
+    if state.ui.model_field_textarea.is_some() {
+        handle_model_field_edit(hotkey_action_option, config_focus, state, key_event)
+            .context("handle model field edit")?;
+        return Ok(HandleEventResult::None);
+    }
+    let Some(hotkey_action) = hotkey_action_option else {
+        return Ok(HandleEventResult::None);
+    };
+    let delete_confirmation = state.ui.model_delete_confirmation.take();
+    if let Some(remote_model_index) = state.ui.remote_model_selection {
+        handle_remote_models(hotkey_action, config_focus, remote_model_index, state)
+            .context("handle remote models")?;
+        return Ok(HandleEventResult::None);
+    }
+    let provider = config_focus.provider();
+    let has_unconfigured = state
+        .remote_models
+        .get(&provider)
+        .is_some_and(|remote_models| {
+            !state
+                .models
+                .get_unconfigured(provider, remote_models)
+                .is_empty()
+        });
+    let is_last_model = state.ui.model_selection.saturating_add(1) >= state.models.count(provider);
+    match (hotkey_action, state.ui.model_field_selection) {
+        (HotkeyAction::Cancel, Some(_)) => state.ui.model_field_selection = None,
+        (HotkeyAction::Cancel, None) => state.ui.focus.set_tab(TabFocus::Chat),
         (HotkeyAction::Edit, _) => {
             actions::edit_config_file_in_editor(state)?;
             state.reload_config()?;
@@ -287,10 +1288,240 @@ fn handle_config(
         (HotkeyAction::Refresh, _) => {
             state.reload_models()?;
             state.reload_config()?;
+            state.start_fetching_remote_models();
+        }
+        (HotkeyAction::CycleProvider, _) => {
+            state.ui.focus.cycle_config_next();
+            state.ui.model_selection = 0;
+            state.ui.model_field_selection = None;
+        }
+        (HotkeyAction::SelectionDown, None) if is_last_model && has_unconfigured => {
+            state.ui.remote_model_selection = Some(0);
+        }
+        // Deleting a model requires pressing clear twice
+        (HotkeyAction::Clear, _)
+            if delete_confirmation != Some(state.ui.model_selection)
+                && state.ui.model_selection < state.models.count(provider) =>
+        {
+            state.ui.model_delete_confirmation = Some(state.ui.model_selection);
+            state.set_status_bar_warning("Clear again to delete the selected model.");
+        }
+        _ => {
+            let status = match provider {
+                Provider::OpenAi => edit_models(
+                    hotkey_action,
+                    &mut state.models.openai,
+                    provider,
+                    &mut state.ui,
+                ),
+                Provider::Anthropic => edit_models(
+                    hotkey_action,
+                    &mut state.models.anthropic,
+                    provider,
+                    &mut state.ui,
+                ),
+            };
+            if let Some(status) = status {
+                save_models(state, provider, &status)?;
+            }
+        }
+    }
+    Ok(HandleEventResult::None)
+}
+
+/// Apply a model editor action to the models of the provider, returns a status message if the
+/// models were changed.
+fn edit_models<M: ModelEntry>(
+    hotkey_action: HotkeyAction,
+    models: &mut Vec<M>,
+    provider: Provider,
+    ui: &mut Ui,
+) -> Option<String> {
+    let fields = ModelField::all(provider);
+    let last_model = models.len().saturating_sub(1);
+    let selection = ui.model_selection.min(last_model);
+    match (hotkey_action, ui.model_field_selection) {
+        (HotkeyAction::SelectionUp, None) => ui.model_selection = selection.saturating_sub(1),
+        (HotkeyAction::SelectionDown, None) => {
+            ui.model_selection = selection.saturating_add(1).min(last_model);
+        }
+        (HotkeyAction::SelectionUp, Some(field_index)) => {
+            ui.model_field_selection = Some(field_index.saturating_sub(1));
+        }
+        (HotkeyAction::SelectionDown, Some(field_index)) => {
+            let last_field = fields.len().saturating_sub(1);
+            ui.model_field_selection = Some(field_index.saturating_add(1).min(last_field));
+        }
+        (HotkeyAction::Select, None) if !models.is_empty() => ui.model_field_selection = Some(0),
+        (HotkeyAction::Select, Some(field_index)) => {
+            let field = *fields.get(field_index)?;
+            if field.is_text() {
+                let value = models.get(selection)?.get_field(field).unwrap_or_default();
+                let mut textarea = TextArea::new(vec![value]);
+                textarea.move_cursor(CursorMove::End);
+                ui.model_field_textarea = Some(textarea);
+            }
+        }
+        (HotkeyAction::Increment | HotkeyAction::Decrement, Some(field_index)) => {
+            let field = *fields.get(field_index)?;
+            if !field.is_adjustable() {
+                return None;
+            }
+            let model = models.get_mut(selection)?;
+            model.step_field(field, hotkey_action == HotkeyAction::Increment);
+            return Some(format!("Changed {field} of {}.", model.name()));
+        }
+        (HotkeyAction::New, _) => {
+            models.push(M::default());
+            ui.model_selection = models.len().saturating_sub(1);
+            ui.model_field_selection = Some(0);
+            return Some("Added model.".to_owned());
+        }
+        (HotkeyAction::Copy, _) => {
+            let mut model = models.get(selection)?.clone();
+            model.set_text_field(ModelField::Id, format!("{}-copy", model.id()));
+            model.set_text_field(ModelField::Name, format!("{} (copy)", model.name()));
+            models.insert(selection.saturating_add(1), model);
+            ui.model_selection = selection.saturating_add(1);
+            return Some("Duplicated model.".to_owned());
+        }
+        (HotkeyAction::Clear, _) if selection < models.len() => {
+            let model = models.remove(selection);
+            ui.model_selection = selection.min(models.len().saturating_sub(1));
+            ui.model_field_selection = None;
+            return Some(format!("Deleted model {}.", model.name()));
         }
         _ => (),
+    }
+    None
+}
+
+fn handle_remote_models(
+    hotkey_action: HotkeyAction,
+    config_focus: ConfigFocus,
+    remote_model_index: usize,
+    state: &mut State,
+) -> Result<()> {
+    let provider = config_focus.provider();
+    let remote_models = state
+        .remote_models
+        .get(&provider)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let unconfigured = state.models.get_unconfigured(provider, remote_models);
+    let last_index = unconfigured.len().saturating_sub(1);
+    match hotkey_action {
+        HotkeyAction::Cancel => state.ui.remote_model_selection = None,
+        HotkeyAction::CycleProvider => {
+            state.ui.focus.cycle_config_next();
+            state.ui.model_selection = 0;
+            state.ui.remote_model_selection = None;
+        }
+        HotkeyAction::SelectionUp => {
+            state.ui.remote_model_selection = remote_model_index.checked_sub(1);
+        }
+        HotkeyAction::SelectionDown => {
+            state.ui.remote_model_selection =
+                Some(remote_model_index.saturating_add(1).min(last_index));
+        }
+        HotkeyAction::Select => {
+            let Some(remote_model) = unconfigured.get(remote_model_index).copied().cloned() else {
+                return Ok(());
+            };
+            let has_pricing = state.models.add_remote(provider, &remote_model);
+            // Keep the selection on the next unconfigured model, or go back to the model list
+            let remaining = last_index;
+            state.ui.remote_model_selection =
+                (remaining > 0).then_some(remote_model_index.min(remaining.saturating_sub(1)));
+            if state.ui.remote_model_selection.is_none() {
+                state.ui.model_selection = state.models.count(provider).saturating_sub(1);
+            }
+            save_models(
+                state,
+                provider,
+                &format!("Added model {}.", remote_model.name),
+            )?;
+            if !has_pricing {
+                state.set_status_bar_warning(format!(
+                    "Added model {} without pricing or context window, set them in the models \
+                    file to track costs.",
+                    remote_model.name
+                ));
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+fn handle_model_field_edit(
+    hotkey_action_option: Option<HotkeyAction>,
+    config_focus: ConfigFocus,
+    state: &mut State,
+    key_event: KeyEvent,
+) -> Result<()> {
+    match hotkey_action_option {
+        Some(HotkeyAction::Cancel) => state.ui.model_field_textarea = None,
+        Some(HotkeyAction::Select) => {
+            let Some(textarea) = state.ui.model_field_textarea.as_ref() else {
+                return Ok(());
+            };
+            let value = textarea.lines().join(" ").trim().to_owned();
+            if value.is_empty() {
+                state.set_status_bar_warning("Model fields cannot be empty.");
+                return Ok(());
+            }
+            state.ui.model_field_textarea = None;
+            let provider = config_focus.provider();
+            let field_option = state
+                .ui
+                .model_field_selection
+                .and_then(|field_index| ModelField::all(provider).get(field_index).copied());
+            let Some(field) = field_option else {
+                return Ok(());
+            };
+            let selection = state.ui.model_selection;
+            let changed = match provider {
+                Provider::OpenAi => {
+                    set_model_text_field(&mut state.models.openai, selection, field, value)
+                }
+                Provider::Anthropic => {
+                    set_model_text_field(&mut state.models.anthropic, selection, field, value)
+                }
+            };
+            if changed {
+                save_models(state, provider, &format!("Changed {field}."))?;
+            }
+        }
+        _ => {
+            if let Some(textarea) = state.ui.model_field_textarea.as_mut() {
+                textarea.input(key_event);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn set_model_text_field<M: ModelEntry>(
+    models: &mut [M],
+    selection: usize,
+    field: ModelField,
+    value: String,
+) -> bool {
+    let Some(model) = models.get_mut(selection) else {
+        return false;
     };
-    Ok(HandleEventResult::None)
+    model.set_text_field(field, value);
+    true
+}
+
+fn save_models(state: &mut State, provider: Provider, status: &str) -> Result<()> {
+    let models_file = state
+        .models
+        .to_disk(&state.paths.models_dir, provider)
+        .context("save models to disk")?;
+    state.set_status_bar_text(format!("{status} Saved to {}", models_file.display()));
+    Ok(())
 }
 
 fn handle_debug(hotkey_action: HotkeyAction, state: &mut State) {
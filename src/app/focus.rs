@@ -1,5 +1,6 @@
 use crate::api::Provider;
 use anyhow::Context;
+use serde::{Deserialize, Serialize};
 use strum::{EnumIter, IntoEnumIterator};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -15,6 +16,7 @@ impl Focus {
         match self.tab {
             Tab::Chat => Scope::Chat(self.chat),
             Tab::Config => Scope::Config(self.config),
+            Tab::Stats => Scope::Stats,
             Tab::Debug => Scope::Debug,
         }
     }
@@ -99,19 +101,32 @@ impl Focus {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy, EnumIter)]
+#[derive(Debug, PartialEq, Clone, Copy, EnumIter, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Tab {
     Chat,
     Config,
+    Stats,
     Debug,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Chat {
     Messages,
     Prompt,
     New,
     History,
+    CodeBlocks,
+    CodeBlockSave,
+    Patches,
+    Sidebar,
+    Rename,
+    Parameters,
+    PresetRename,
+    Snippets,
+    SnippetSave,
+    PromptSearch,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy, EnumIter)]
@@ -128,11 +143,20 @@ impl Config {
             Provider::Anthropic => Self::Anthropic,
         }
     }
+
+    #[must_use]
+    pub fn provider(self) -> Provider {
+        match self {
+            Self::OpenAi => Provider::OpenAi,
+            Self::Anthropic => Provider::Anthropic,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Scope {
     Chat(Chat),
     Config(Config),
+    Stats,
     Debug,
 }
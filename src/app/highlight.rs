@@ -0,0 +1,182 @@
+use crate::chat::parse_code_blocks;
+use ratatui::prelude::{Color, Line, Modifier, Span, Style};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Style as SyntectStyle, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+
+const MAX_CACHED_TEXTS: usize = 1000;
+
+/// Syntax highlighting for fenced code blocks in message text.
+pub struct Highlighter {
+    syntax_set: SyntaxSet,
+    theme_set: ThemeSet,
+    cache: HashMap<u64, Vec<HighlightedLine>>,
+}
+
+#[derive(Debug, Clone)]
+enum HighlightedLine {
+    Text(String),
+    Fence(String),
+    Code(Vec<(Option<SyntectStyle>, String)>),
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Highlighter {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            syntax_set: SyntaxSet::load_defaults_newlines(),
+            theme_set: ThemeSet::load_defaults(),
+            cache: HashMap::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn has_theme(&self, theme_name: &str) -> bool {
+        self.theme_set.themes.contains_key(theme_name)
+    }
+
+    #[must_use]
+    pub fn theme_names(&self) -> Vec<&str> {
+        self.theme_set.themes.keys().map(String::as_str).collect()
+    }
+
+    /// Get the conventional file extension for a code block language tag.
+    #[must_use]
+    pub fn file_extension(&self, language: &str) -> Option<&str> {
+        self.syntax_set
+            .find_syntax_by_token(language)
+            .and_then(|syntax| syntax.file_extensions.first())
+            .map(String::as_str)
+    }
+
+    /// Convert text to styled lines, highlighting the code in fenced code blocks.
+    ///
+    /// Code blocks with an unknown language (or if the theme is missing) are drawn with the
+    /// text style.
+    pub fn highlight_text(
+        &mut self,
+        text: &str,
+        theme_name: &str,
+        text_style: Style,
+        fence_style: Style,
+    ) -> Vec<Line<'static>> {
+        let mut hasher = DefaultHasher::new();
+        (text, theme_name).hash(&mut hasher);
+        let key = hasher.finish();
+        if !self.cache.contains_key(&key) {
+            if self.cache.len() >= MAX_CACHED_TEXTS {
+                self.cache.clear();
+            }
+            let highlighted = self.highlight(text, self.theme_set.themes.get(theme_name));
+            self.cache.insert(key, highlighted);
+        }
+        self.cache
+            .get(&key)
+            .map(|highlighted| {
+                highlighted
+                    .iter()
+                    .map(|line| line.to_line(text_style, fence_style))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn highlight(&self, text: &str, theme: Option<&Theme>) -> Vec<HighlightedLine> {
+        let blocks = parse_code_blocks(text);
+        let mut blocks_iter = blocks.iter().peekable();
+        let mut block_highlighter: Option<HighlightLines> = None;
+        let mut lines = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            while blocks_iter
+                .peek()
+                .is_some_and(|block| block.fence_end.is_some_and(|end| end < index))
+            {
+                blocks_iter.next();
+                block_highlighter = None;
+            }
+            let Some(block) = blocks_iter.peek() else {
+                lines.push(HighlightedLine::Text(line.to_owned()));
+                continue;
+            };
+            if block.is_fence_line(index) {
+                if index == block.fence_start {
+                    block_highlighter =
+                        theme
+                            .zip(block.language.as_deref())
+                            .and_then(|(theme, language)| {
+                                self.syntax_set
+                                    .find_syntax_by_token(language)
+                                    .map(|syntax| HighlightLines::new(syntax, theme))
+                            });
+                }
+                lines.push(HighlightedLine::Fence(line.to_owned()));
+            } else if block.contains_code_line(index) {
+                lines.push(self.highlight_code_line(line, block_highlighter.as_mut()));
+            } else {
+                lines.push(HighlightedLine::Text(line.to_owned()));
+            }
+        }
+        lines
+    }
+
+    fn highlight_code_line(
+        &self,
+        line: &str,
+        highlighter: Option<&mut HighlightLines>,
+    ) -> HighlightedLine {
+        let unstyled = || HighlightedLine::Code(vec![(None, line.to_owned())]);
+        let Some(highlighter) = highlighter else {
+            return unstyled();
+        };
+        // Syntaxes are loaded for newline-terminated lines
+        let line_with_ending = format!("{line}\n");
+        match highlighter.highlight_line(&line_with_ending, &self.syntax_set) {
+            Ok(ranges) => HighlightedLine::Code(
+                ranges
+                    .into_iter()
+                    .map(|(style, text)| (Some(style), text.trim_end_matches('\n').to_owned()))
+                    .collect(),
+            ),
+            Err(_) => unstyled(),
+        }
+    }
+}
+
+impl HighlightedLine {
+    fn to_line(&self, text_style: Style, fence_style: Style) -> Line<'static> {
+        match self {
+            Self::Text(text) => Line::styled(text.clone(), text_style),
+            Self::Fence(text) => Line::styled(text.clone(), fence_style),
+            Self::Code(spans) => spans
+                .iter()
+                .map(|(style, text)| {
+                    let style = style.map_or(text_style, |style| convert_style(style, text_style));
+                    Span::styled(text.clone(), style)
+                })
+                .collect(),
+        }
+    }
+}
+
+fn convert_style(style: SyntectStyle, base: Style) -> Style {
+    let foreground = style.foreground;
+    let mut converted = base.fg(Color::Rgb(foreground.r, foreground.g, foreground.b));
+    if style.font_style.contains(FontStyle::BOLD) {
+        converted = converted.add_modifier(Modifier::BOLD);
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        converted = converted.add_modifier(Modifier::ITALIC);
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        converted = converted.add_modifier(Modifier::UNDERLINED);
+    }
+    converted
+}
@@ -31,6 +31,21 @@ pub enum HotkeyAction {
     Decrement,
     CycleTab,
     CycleBackTab,
+    PreviousMessage,
+    NextMessage,
+    CodeBlocks,
+    Patch,
+    Save,
+    ToggleSidebar,
+    Rename,
+    ToggleMetadata,
+    CycleContext,
+    ToggleIncluded,
+    TogglePinned,
+    Parameters,
+    CycleProvider,
+    Snippets,
+    SearchHistory,
 }
 
 #[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
@@ -1,73 +1,210 @@
 use crate::{
-    app::hotkeys,
-    chat::Conversation,
-    config::{Config, Models},
+    api::{ContextUsage, Provider, RemoteModel, TokenCounter},
+    app::{highlight::Highlighter, hotkeys},
+    chat::{
+        expand_template, Conversation, ConversationStore, Message, Metadata, PromptHistory, Stats,
+    },
+    config::{
+        filter_snippets, get_preset_differences, load_presets, load_snippets, Config, Models,
+        Snippet,
+    },
+    usage::{Ledger, LedgerEntry, LimitStatus},
 };
 use anyhow::{Context, Result};
-use std::io::Write;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+const DRAFT_SAVE_DELAY: Duration = Duration::from_secs(2);
+
+type TitleTask = JoinHandle<Result<(String, Metadata)>>;
+type RemoteModelsTask = JoinHandle<Vec<(Provider, Result<Vec<RemoteModel>>)>>;
 
 mod paths;
+mod session;
 mod ui;
 
 pub use paths::Paths;
+pub use session::Session;
+pub use ui::{PromptKey, Ui, SIDEBAR_ITEM_HEIGHT};
 
 pub struct State {
     pub config: Config,
     pub models: Models,
     pub hotkey_map: hotkeys::HotkeyMap,
     pub paths: Paths,
-    pub conversations: Vec<Conversation>,
+    pub conversations: ConversationStore,
+    pub snippets: Vec<Snippet>,
     pub ui: ui::Ui,
+    pub highlighter: Highlighter,
+    pub ledger: Ledger,
+    pub prompt_history: PromptHistory,
+    pub token_counter: TokenCounter,
+    /// Models listed by the provider APIs, fetched from the config tab.
+    pub remote_models: HashMap<Provider, Vec<RemoteModel>>,
+    /// Statistics of the saved conversations, `None` until computed or after changes.
+    pub stats: Option<Stats>,
+    /// Titles being generated in the background, by conversation id.
+    title_tasks: Vec<(String, TitleTask)>,
+    /// Models being fetched from the provider APIs in the background.
+    remote_models_task: Option<RemoteModelsTask>,
 }
 
 impl State {
     pub fn new() -> Result<Self> {
         let paths = Paths::generate_dirs().context("generate directories")?;
-        let config =
+        let mut config =
             Config::from_file(&paths.get_config_file(), true).context("get config from disk")?;
+        let presets = load_presets(&paths.system_dir, &config.system.instructions)
+            .context("load system instructions presets")?;
+        let preset_differences = get_preset_differences(&config.system.instructions, &presets);
+        config.system.instructions = presets;
         let models = Models::from_disk(&paths.models_dir, true).context("get models from disk")?;
         let hotkey_map = hotkeys::get_hotkey_config(config.hotkeys.clone());
-        let system_instructions = config
-            .system
-            .instructions
-            .first()
-            .context("no system instructions")?
-            .message
-            .clone();
-        let mut conversations =
-            Self::load_conversations_from_disk(&paths.get_conversations_file())?;
-        conversations.insert(0, Conversation::new(system_instructions));
+        let snippets = load_snippets(&paths.snippets_dir).context("load snippets")?;
+        let (mut conversations, conversations_warnings) =
+            ConversationStore::from_dir(&paths.conversations_dir).context("load conversations")?;
+        let migrated_count = conversations
+            .migrate_from_file(&paths.get_conversations_file())
+            .context("migrate conversations file")?;
+        let (ledger, ledger_errors) =
+            Ledger::from_file(&paths.get_ledger_file()).context("load ledger")?;
+        let (prompt_history, prompt_history_errors) =
+            PromptHistory::from_file(&paths.get_prompt_history_file(), config.history.max_entries)
+                .context("load prompt history")?;
 
-        let ui = ui::Ui::with_provider(config.provider);
+        let ui = ui::Ui::new(config.provider, &config.ui.layout);
         let mut state = Self {
             config,
             models,
             hotkey_map,
             paths,
             conversations,
+            snippets,
             ui,
+            highlighter: Highlighter::new(),
+            ledger,
+            prompt_history,
+            token_counter: TokenCounter::new(),
+            remote_models: HashMap::new(),
+            stats: None,
+            title_tasks: Vec::new(),
+            remote_models_task: None,
         };
         state.set_status_bar_text(format!(
             "Config file: {}",
             state.paths.get_config_file().display()
         ));
         state.add_debug_log("Initialized debug logs");
+        for difference in preset_differences {
+            state.add_debug_log(difference);
+        }
+        for warning in conversations_warnings {
+            state.add_debug_log(format!("Conversations: {warning}"));
+        }
+        for error in ledger_errors {
+            state.add_debug_log(format!("Ledger: {error}"));
+        }
+        for error in prompt_history_errors {
+            state.add_debug_log(format!("Prompt history: {error}"));
+        }
+        if migrated_count > 0 {
+            state.add_debug_log(format!(
+                "Migrated {migrated_count} conversations to: {}",
+                state.paths.conversations_dir.display()
+            ));
+        }
+        let system_instructions = state
+            .config
+            .system
+            .instructions
+            .first()
+            .context("no system instructions")?
+            .message
+            .clone();
+        let system_instructions = state.expand_template(&system_instructions);
+        let session = if state.config.session.restore {
+            state.load_session()
+        } else {
+            None
+        };
+        // A restored session reuses an empty first conversation instead of adding another
+        let reuse_first =
+            session.is_some() && state.conversations.get(0).is_ok_and(Conversation::is_empty);
+        if !reuse_first {
+            state
+                .conversations
+                .insert(0, Conversation::new(system_instructions));
+        }
+        if let Some(session) = session {
+            let conversation_index = session
+                .conversation_id
+                .as_deref()
+                .and_then(|id| state.conversations.position(id))
+                .unwrap_or_default();
+            session.apply(&mut state.ui, conversation_index);
+            state.fix_clamp_ui_selections();
+            let draft = state.get_active_conversation()?.draft.clone();
+            state.ui.set_prompt_text(&draft);
+            state.ui.prompt_conversation_index = state.ui.active_conversation_index;
+        }
+        state.check_highlight_theme();
         Ok(state)
     }
 
     pub fn reload_config(&mut self) -> Result<()> {
         self.config = Config::from_file(&self.paths.get_config_file(), false)
             .context("get config from file")?;
+        self.reload_system_presets()?;
+        self.reload_snippets()?;
         self.hotkey_map = hotkeys::get_hotkey_config(self.config.hotkeys.clone());
         self.set_status_bar_text(format!(
             "Reloaded config file: {}",
             self.paths.get_config_file().display()
         ));
         self.add_debug_log("Reloaded config file.");
+        self.check_highlight_theme();
+        Ok(())
+    }
+
+    /// Load the system instructions presets from the system directory.
+    pub fn reload_system_presets(&mut self) -> Result<()> {
+        self.config.system.instructions =
+            load_presets(&self.paths.system_dir, &self.config.system.instructions)
+                .context("load system instructions presets")?;
+        let max_selection = self.config.system.instructions.len().saturating_sub(1);
+        self.ui.system_instruction_selection =
+            self.ui.system_instruction_selection.min(max_selection);
         Ok(())
     }
 
+    /// Load the snippets from the snippets directory.
+    pub fn reload_snippets(&mut self) -> Result<()> {
+        self.snippets = load_snippets(&self.paths.snippets_dir).context("load snippets")?;
+        Ok(())
+    }
+
+    /// Snippets matching the snippet filter, best matches first.
+    #[must_use]
+    pub fn get_filtered_snippets(&self) -> Vec<&Snippet> {
+        let query = self.ui.snippet_filter_textarea.lines().join(" ");
+        filter_snippets(&self.snippets, &query)
+            .into_iter()
+            .filter_map(|index| self.snippets.get(index))
+            .collect()
+    }
+
+    fn check_highlight_theme(&mut self) {
+        let theme = &self.config.ui.highlight.theme;
+        if !self.highlighter.has_theme(theme) {
+            let log = format!(
+                "Unknown highlight theme '{theme}', code will not be highlighted. Available themes: {}",
+                self.highlighter.theme_names().join(", ")
+            );
+            self.add_debug_log(log);
+        }
+    }
+
     pub fn reload_models(&mut self) -> Result<()> {
         self.models =
             Models::from_disk(&self.paths.models_dir, false).context("get models from disk")?;
@@ -79,26 +216,342 @@ impl State {
         Ok(())
     }
 
+    /// Estimated tokens of a request of the active conversation with an additional message,
+    /// reserving the max tokens of the reply.
+    pub fn get_context_usage(&mut self, additional_message: &str) -> Option<ContextUsage> {
+        let (model_id, context_window) = crate::api::get_active_model_context(self)?;
+        let model_parameters = crate::api::get_active_model_parameters(self);
+        let conversation = self
+            .conversations
+            .get(self.ui.active_conversation_index)
+            .ok()?;
+        let reply_tokens = conversation
+            .parameters
+            .with_defaults(&model_parameters)
+            .max_tokens
+            .unwrap_or_default();
+        let prompt_tokens = self.token_counter.count_conversation(
+            self.config.provider,
+            &model_id,
+            conversation,
+            additional_message,
+        );
+        Some(ContextUsage {
+            prompt_tokens,
+            reply_tokens,
+            context_window,
+        })
+    }
+
+    /// Index of the first message of the active conversation in the context of the active model.
+    pub fn get_context_start(&mut self) -> usize {
+        let Some((model_id, _)) = crate::api::get_active_model_context(self) else {
+            return 0;
+        };
+        let Ok(conversation) = self.conversations.get(self.ui.active_conversation_index) else {
+            return 0;
+        };
+        self.token_counter
+            .context_start(self.config.provider, &model_id, conversation)
+    }
+
+    /// Record the usage of a completion in the ledger.
+    pub fn record_usage(&mut self, metadata: &Metadata) {
+        self.stats = None;
+        let Some(entry) = LedgerEntry::from_metadata(metadata) else {
+            return;
+        };
+        if let Err(error) = self.ledger.record(entry) {
+            self.add_debug_log(format!("Failed to record usage in ledger: {error:?}"));
+        }
+    }
+
+    /// Record a sent prompt in the prompt history.
+    pub fn record_prompt(&mut self, prompt: &str) {
+        self.ui.prompt_history_selection = None;
+        self.ui.prompt_draft.clear();
+        if let Err(error) = self.prompt_history.record(prompt) {
+            self.add_debug_log(format!("Failed to record prompt in history: {error:?}"));
+        }
+    }
+
+    /// Keep the prompt in sync with the draft of the active conversation, saving changed drafts
+    /// to disk shortly after editing.
+    ///
+    /// While browsing the prompt history the draft is the prompt from before browsing.
+    pub fn sync_prompt_draft(&mut self) -> Result<()> {
+        let active_index = self.ui.active_conversation_index;
+        if active_index != self.ui.prompt_conversation_index {
+            if self.ui.prompt_history_selection.is_some() {
+                let prompt_draft = std::mem::take(&mut self.ui.prompt_draft);
+                if let Ok(conversation) = self
+                    .conversations
+                    .get_mut(self.ui.prompt_conversation_index)
+                {
+                    if conversation.draft != prompt_draft {
+                        conversation.draft = prompt_draft;
+                        self.ui.draft_changed_at.get_or_insert_with(Instant::now);
+                    }
+                }
+            }
+            self.save_unsaved_draft()?;
+            let draft = self.get_active_conversation()?.draft.clone();
+            self.ui.set_prompt_text(&draft);
+            self.ui.prompt_conversation_index = active_index;
+            self.ui.prompt_history_selection = None;
+            self.ui.prompt_draft.clear();
+            return Ok(());
+        }
+        if self.ui.prompt_history_selection.is_some() {
+            return Ok(());
+        }
+        let text = self.ui.prompt_textarea.lines().join("\n");
+        let conversation = self.get_active_conversation_mut()?;
+        if conversation.draft != text {
+            conversation.draft = text;
+            self.ui.draft_changed_at.get_or_insert_with(Instant::now);
+        }
+        if self
+            .ui
+            .draft_changed_at
+            .is_some_and(|changed_at| changed_at.elapsed() >= DRAFT_SAVE_DELAY)
+        {
+            self.save_unsaved_draft()?;
+        }
+        Ok(())
+    }
+
+    /// Generate a title for the active conversation in the background.
+    ///
+    /// Returns whether generation started, it is skipped once a spending limit is reached.
+    pub fn start_title_generation(&mut self) -> Result<bool> {
+        let id = self
+            .get_active_conversation_id()
+            .context("get active conversation id")?;
+        if self.title_tasks.iter().any(|(task_id, _)| *task_id == id) {
+            return Ok(true);
+        }
+        if let LimitStatus::Exceeded(reason) = self
+            .ledger
+            .check_limits(self.config.provider, &self.config.limits)
+        {
+            self.add_debug_log(format!("Skipped title generation: {reason}"));
+            return Ok(false);
+        }
+        let future = crate::api::generate_title(self, self.get_active_conversation()?)
+            .context("prepare title generation")?;
+        self.title_tasks.push((id, tokio::spawn(future)));
+        Ok(true)
+    }
+
+    /// Set the titles that finished generating, unless the conversation was renamed meanwhile.
+    pub async fn collect_generated_titles(&mut self) -> Result<()> {
+        let (finished, pending) = std::mem::take(&mut self.title_tasks)
+            .into_iter()
+            .partition(|(_, task)| task.is_finished());
+        self.title_tasks = pending;
+        for (id, task) in finished {
+            let (title, metadata) = match task.await.context("join title generation task")? {
+                Ok(generated) => generated,
+                Err(error) => {
+                    self.add_debug_log(format!("Failed to generate title: {error:?}"));
+                    continue;
+                }
+            };
+            self.record_usage(&metadata);
+            let Some(index) = self.conversations.position(&id) else {
+                continue;
+            };
+            let conversation = self.conversations.get_mut(index)?;
+            if conversation.title.is_some() {
+                continue;
+            }
+            conversation.title = Some(title.clone());
+            self.conversations
+                .save(index)
+                .context("save conversation")?;
+            self.add_debug_log(format!("Generated conversation title: {title}"));
+        }
+        Ok(())
+    }
+
+    /// Fetch the models of all providers in the background.
+    pub fn start_fetching_remote_models(&mut self) {
+        if self.remote_models_task.is_some() {
+            return;
+        }
+        let openai = crate::api::get_remote_models(self, Provider::OpenAi);
+        let anthropic = crate::api::get_remote_models(self, Provider::Anthropic);
+        self.remote_models_task = Some(tokio::spawn(async move {
+            let (openai, anthropic) = tokio::join!(openai, anthropic);
+            vec![(Provider::OpenAi, openai), (Provider::Anthropic, anthropic)]
+        }));
+        self.set_status_bar_text("Fetching models...");
+    }
+
+    /// Store the fetched models once fetching finished and report the ones that are not
+    /// configured.
+    pub async fn collect_remote_models(&mut self) -> Result<()> {
+        if !self
+            .remote_models_task
+            .as_ref()
+            .is_some_and(JoinHandle::is_finished)
+        {
+            return Ok(());
+        }
+        let Some(task) = self.remote_models_task.take() else {
+            return Ok(());
+        };
+        let mut unconfigured_counts = Vec::new();
+        let mut failed = false;
+        for (provider, result) in task.await.context("join remote models task")? {
+            match result {
+                Ok(remote_models) => {
+                    let unconfigured_count =
+                        self.models.get_unconfigured(provider, &remote_models).len();
+                    unconfigured_counts.push(format!("{unconfigured_count} {provider}"));
+                    self.remote_models.insert(provider, remote_models);
+                }
+                Err(error) => {
+                    failed = true;
+                    self.add_debug_log(format!("Failed to fetch {provider} models: {error:#}"));
+                }
+            }
+        }
+        let status = format!(
+            "Models not configured yet: {}",
+            unconfigured_counts.join(", ")
+        );
+        if failed {
+            self.set_status_bar_warning(format!("{status} (fetching failed, see debug logs)"));
+        } else {
+            self.set_status_bar_text(status);
+        }
+        Ok(())
+    }
+
+    fn load_session(&mut self) -> Option<Session> {
+        match Session::from_file(&self.paths.get_session_file()) {
+            Ok(session) => session,
+            Err(error) => {
+                self.add_debug_log(format!("Failed to load session: {error:?}"));
+                None
+            }
+        }
+    }
+
+    /// Save the unsaved draft and the session to disk, to be restored on the next launch.
+    pub fn save_session(&mut self) -> Result<()> {
+        self.save_unsaved_draft()?;
+        Session::from_ui(&self.ui, self.get_active_conversation_id())
+            .to_file(&self.paths.get_session_file())
+            .context("save session")
+    }
+
+    /// Save the conversations to disk if a draft was changed since they were last saved.
+    pub fn save_unsaved_draft(&mut self) -> Result<()> {
+        if self.ui.draft_changed_at.take().is_some() {
+            self.conversations
+                .save(self.ui.prompt_conversation_index)
+                .context("save conversation")?;
+        }
+        Ok(())
+    }
+
     pub fn fix_clamp_ui_selections(&mut self) {
         if self.ui.active_conversation_index >= self.conversations.len() {
             self.ui.active_conversation_index = self.conversations.len() - 1;
         }
+        self.ui.message_selection = self
+            .ui
+            .message_selection
+            .and(self.get_selected_message_index());
     }
 
     pub fn get_active_conversation(&self) -> Result<&Conversation> {
         self.conversations
             .get(self.ui.active_conversation_index)
-            .context("active conversation index out of bounds")
+            .context("get active conversation")
+    }
+
+    #[must_use]
+    pub fn get_active_conversation_id(&self) -> Option<String> {
+        self.conversations
+            .entries()
+            .nth(self.ui.active_conversation_index)
+            .map(|entry| entry.id.clone())
+    }
+
+    /// The prompt being edited, to confirm sending it.
+    #[must_use]
+    pub fn get_prompt_key(&self) -> PromptKey {
+        PromptKey {
+            conversation_id: self.get_active_conversation_id(),
+            text: self.ui.prompt_textarea.lines().join("\n"),
+        }
     }
 
     pub fn get_active_conversation_mut(&mut self) -> Result<&mut Conversation> {
         self.conversations
             .get_mut(self.ui.active_conversation_index)
-            .context("active conversation index out of bounds")
+            .context("get active conversation")
+    }
+
+    /// Index of the selected message in the active conversation, `None` if there are no messages.
+    #[must_use]
+    pub fn get_selected_message_index(&self) -> Option<usize> {
+        let message_count = self.get_active_conversation().ok()?.messages.len();
+        let last_index = message_count.checked_sub(1)?;
+        Some(
+            self.ui
+                .message_selection
+                .map_or(last_index, |index| index.min(last_index)),
+        )
+    }
+
+    pub fn get_selected_message(&self) -> Result<&Message> {
+        let index = self
+            .get_selected_message_index()
+            .context("no messages in conversation")?;
+        self.get_active_conversation()?
+            .messages
+            .get(index)
+            .context("selected message index out of bounds")
+    }
+
+    pub fn get_selected_message_mut(&mut self) -> Result<&mut Message> {
+        let index = self
+            .get_selected_message_index()
+            .context("no messages in conversation")?;
+        self.get_active_conversation_mut()?
+            .messages
+            .get_mut(index)
+            .context("selected message index out of bounds")
+    }
+
+    /// Expand the template variables in the text, logging variables that failed to resolve.
+    pub fn expand_template(&mut self, text: &str) -> String {
+        let mut errors = Vec::new();
+        let expanded = expand_template(text, |name| {
+            self.config.variables.resolve(name).unwrap_or_else(|error| {
+                errors.push(format!("Failed to expand variable '{name}': {error:?}"));
+                None
+            })
+        });
+        for error in errors {
+            self.add_debug_log(error);
+        }
+        expanded
     }
 
     pub fn set_status_bar_text<T: Into<String>>(&mut self, text: T) {
         self.ui.status_bar_text = text.into();
+        self.ui.status_bar_warn = false;
+    }
+
+    pub fn set_status_bar_warning<T: Into<String>>(&mut self, text: T) {
+        self.ui.status_bar_text = text.into();
+        self.ui.status_bar_warn = true;
     }
 
     pub fn add_debug_log<T: Into<String>>(&mut self, log: T) {
@@ -107,25 +560,21 @@ impl State {
             .push(format!("{} | {}", crate::get_timestamp(), log.into()));
     }
 
-    pub fn save_conversations_to_disk(&self) -> Result<()> {
-        let data =
-            serde_json::to_string_pretty(&self.conversations).context("serialize conversations")?;
-        let save_file_path = self.paths.data_dir.join("conversations.json");
-        let mut file =
-            std::fs::File::create(save_file_path).context("create conversations file")?;
-        file.write_all(data.as_bytes())
-            .context("write conversations file")?;
-        Ok(())
+    pub fn save_active_conversation(&mut self) -> Result<()> {
+        self.stats = None;
+        self.conversations.save(self.ui.active_conversation_index)
     }
 
-    pub fn load_conversations_from_disk(save_file_path: &PathBuf) -> Result<Vec<Conversation>> {
-        if save_file_path.is_file() {
-            let data =
-                std::fs::read_to_string(save_file_path).context("read conversations file")?;
-            let conversations = serde_json::from_str(&data).context("deserialize conversations")?;
-            Ok(conversations)
-        } else {
-            Ok(Vec::new())
+    /// Compute the statistics if the conversations changed since they were last computed,
+    /// conversations that fail to load are left out.
+    pub fn update_stats(&mut self) {
+        if self.stats.is_some() {
+            return;
+        }
+        let (conversations, errors) = self.conversations.load_all();
+        self.stats = Some(Stats::from_conversations(conversations));
+        for error in errors {
+            self.add_debug_log(format!("Stats skipped conversation {error}"));
         }
     }
 }
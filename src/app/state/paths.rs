@@ -5,6 +5,12 @@ pub struct Paths {
     pub data_dir: PathBuf,
     pub config_dir: PathBuf,
     pub models_dir: PathBuf,
+    /// System instructions presets, one file per preset.
+    pub system_dir: PathBuf,
+    /// Prompt snippets, one file per snippet.
+    pub snippets_dir: PathBuf,
+    /// Conversations, one file per conversation and an index file.
+    pub conversations_dir: PathBuf,
 }
 
 impl Paths {
@@ -13,22 +19,38 @@ impl Paths {
             .context("get config directory")?
             .join(crate::APP_TITLE.to_lowercase());
         let models_dir = config_dir.join("models");
+        let system_dir = config_dir.join("system");
+        let snippets_dir = config_dir.join("snippets");
         let data_dir = dirs::data_dir()
             .context("get data directory")?
             .join(crate::APP_TITLE.to_lowercase());
+        let conversations_dir = data_dir.join("conversations");
         if !config_dir.exists() {
             std::fs::create_dir_all(&config_dir).context("create config directory")?;
         }
         if !models_dir.exists() {
             std::fs::create_dir_all(&models_dir).context("create models directory")?;
         }
+        if !system_dir.exists() {
+            std::fs::create_dir_all(&system_dir).context("create system directory")?;
+        }
+        if !snippets_dir.exists() {
+            std::fs::create_dir_all(&snippets_dir).context("create snippets directory")?;
+        }
         if !data_dir.exists() {
             std::fs::create_dir_all(&data_dir).context("create data directory")?;
         }
+        if !conversations_dir.exists() {
+            std::fs::create_dir_all(&conversations_dir)
+                .context("create conversations directory")?;
+        }
         Ok(Self {
             data_dir,
             config_dir,
             models_dir,
+            system_dir,
+            snippets_dir,
+            conversations_dir,
         })
     }
 
@@ -42,6 +64,27 @@ impl Paths {
         self.data_dir.join("message_text")
     }
 
+    #[must_use]
+    pub fn get_code_block_file(&self, extension: &str) -> PathBuf {
+        self.data_dir.join(format!("code_block.{extension}"))
+    }
+
+    #[must_use]
+    pub fn get_ledger_file(&self) -> PathBuf {
+        self.data_dir.join("ledger.jsonl")
+    }
+
+    #[must_use]
+    pub fn get_prompt_history_file(&self) -> PathBuf {
+        self.data_dir.join("prompt_history.jsonl")
+    }
+
+    #[must_use]
+    pub fn get_session_file(&self) -> PathBuf {
+        self.data_dir.join("session.json")
+    }
+
+    /// Conversations file of older versions, migrated to the conversations directory.
     #[must_use]
     pub fn get_conversations_file(&self) -> PathBuf {
         self.data_dir.join("conversations.json")
@@ -0,0 +1,59 @@
+use crate::app::focus::{Chat as ChatFocus, Tab};
+use crate::app::state::Ui;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Position in the UI when the program was last closed, restored on launch.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Session {
+    #[serde(default)]
+    pub conversation_id: Option<String>,
+    pub tab: Tab,
+    pub chat_focus: ChatFocus,
+    pub conversation_scroll: u16,
+    pub message_selection: Option<usize>,
+}
+
+impl Session {
+    #[must_use]
+    pub fn from_ui(ui: &Ui, conversation_id: Option<String>) -> Self {
+        Self {
+            conversation_id,
+            tab: ui.focus.tab,
+            chat_focus: ui.focus.chat,
+            conversation_scroll: ui.conversation_scroll,
+            message_selection: ui.message_selection,
+        }
+    }
+
+    /// Restore the session, with the index of the saved conversation.
+    pub fn apply(&self, ui: &mut Ui, conversation_index: usize) {
+        ui.active_conversation_index = conversation_index;
+        ui.focus.tab = self.tab;
+        // Popups are not restored since they depend on state that is not saved
+        ui.focus.chat = match self.chat_focus {
+            ChatFocus::Sidebar if !ui.sidebar_visible => ChatFocus::Messages,
+            ChatFocus::Messages | ChatFocus::Prompt | ChatFocus::Sidebar => self.chat_focus,
+            _ => ChatFocus::Messages,
+        };
+        ui.conversation_scroll = self.conversation_scroll;
+        ui.message_selection = self.message_selection;
+    }
+
+    /// Load the session from file, missing if there is no session file.
+    pub fn from_file(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read_to_string(path) {
+            Ok(data) => serde_json::from_str(&data)
+                .map(Some)
+                .context("deserialize session"),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error).context("read session file"),
+        }
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_string_pretty(self).context("serialize session")?;
+        crate::write_file(path, &data).context("write session file")
+    }
+}
@@ -1,29 +1,166 @@
-use crate::app::focus::Focus;
+use crate::app::focus::{Focus, Tab};
+use crate::chat::PatchPreview;
+use ratatui::layout::Rect;
+use std::time::Instant;
 use tui_textarea::TextArea;
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct Ui {
     pub focus: Focus,
     pub status_bar_text: String,
+    pub status_bar_warn: bool,
     pub prompt_textarea: TextArea<'static>,
+    /// Index of the conversation whose draft is in the prompt.
+    pub prompt_conversation_index: usize,
+    /// When the draft of the active conversation was changed without being saved to disk.
+    pub draft_changed_at: Option<Instant>,
     pub conversation_scroll: u16,
     pub debug_logs: Vec<String>,
     pub debug_logs_scroll: u16,
     pub active_conversation_index: usize,
     pub system_instruction_selection: usize,
+    /// Index of the selected message in the active conversation, `None` follows the last message.
+    pub message_selection: Option<usize>,
+    pub code_block_selection: usize,
+    pub code_block_path_textarea: TextArea<'static>,
+    pub patch_previews: Vec<PatchPreview>,
+    pub patch_selection: usize,
+    pub patch_preview_scroll: u16,
+    pub sidebar_visible: bool,
+    pub metadata_visible: bool,
+    pub rename_textarea: TextArea<'static>,
+    pub parameter_selection: usize,
+    pub model_selection: usize,
+    /// Index of the selected field of the selected model, `None` while selecting a model.
+    pub model_field_selection: Option<usize>,
+    /// Index of the selected remote model that is not configured yet.
+    pub remote_model_selection: Option<usize>,
+    /// Text of the model field being edited.
+    pub model_field_textarea: Option<TextArea<'static>>,
+    /// Index of the model to delete if clear is pressed again.
+    pub model_delete_confirmation: Option<usize>,
+    /// Name of the system instructions preset to delete if clear is pressed again.
+    pub preset_delete_confirmation: Option<String>,
+    /// Name of the snippet to delete if clear is pressed again.
+    pub snippet_delete_confirmation: Option<String>,
+    pub snippet_filter_textarea: TextArea<'static>,
+    /// Index of the recalled prompt history entry, `None` while editing a new prompt.
+    pub prompt_history_selection: Option<usize>,
+    /// Text of the prompt from before recalling the prompt history.
+    pub prompt_draft: String,
+    /// Index of the selected snippet among the snippets matching the filter.
+    pub snippet_selection: usize,
+    /// Prompt to send despite a reached spending limit.
+    pub limit_override: Option<PromptKey>,
+    /// Prompt to send despite exceeding the context window.
+    pub context_override: Option<PromptKey>,
+    pub mouse_areas: MouseAreas,
+}
+
+/// Identifies a prompt by its text and conversation, so that a confirmation to send it does not
+/// carry over to another prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptKey {
+    pub conversation_id: Option<String>,
+    pub text: String,
+}
+
+/// Screen areas from the last drawn frame, used for handling mouse events.
+#[derive(Debug, Default)]
+pub struct MouseAreas {
+    pub tabs: Vec<(Rect, Tab)>,
+    pub conversation: Rect,
+    pub prompt: Rect,
+    pub debug: Rect,
+    /// Inner area of the drawn list and the index of the first visible item.
+    pub list: Rect,
+    pub list_offset: usize,
+    pub sidebar: Rect,
+    pub sidebar_offset: usize,
+}
+
+impl MouseAreas {
+    /// Get the index of the list item at the given screen row.
+    #[must_use]
+    pub fn get_list_index(&self, column: u16, row: u16) -> Option<usize> {
+        get_list_index(self.list, self.list_offset, 1, column, row)
+    }
+
+    /// Get the index of the sidebar item at the given screen row.
+    #[must_use]
+    pub fn get_sidebar_index(&self, column: u16, row: u16) -> Option<usize> {
+        get_list_index(
+            self.sidebar,
+            self.sidebar_offset,
+            SIDEBAR_ITEM_HEIGHT,
+            column,
+            row,
+        )
+    }
+}
+
+/// Number of lines for each conversation in the sidebar.
+pub const SIDEBAR_ITEM_HEIGHT: u16 = 2;
+
+fn get_list_index(
+    area: Rect,
+    offset: usize,
+    item_height: u16,
+    column: u16,
+    row: u16,
+) -> Option<usize> {
+    if !area.contains((column, row).into()) {
+        return None;
+    }
+    let visible_index = row.saturating_sub(area.y) / item_height.max(1);
+    Some(offset.saturating_add(visible_index.into()))
 }
 
 impl Ui {
     #[must_use]
-    pub fn with_provider(provider: crate::api::Provider) -> Self {
+    pub fn new(provider: crate::api::Provider, layout: &crate::config::Layout) -> Self {
         Ui {
             focus: Focus::with_provider(provider),
             status_bar_text: String::default(),
+            status_bar_warn: false,
             prompt_textarea: TextArea::default(),
+            prompt_conversation_index: 0,
+            draft_changed_at: None,
             conversation_scroll: Default::default(),
             debug_logs: Vec::default(),
             debug_logs_scroll: Default::default(),
             active_conversation_index: Default::default(),
             system_instruction_selection: Default::default(),
+            message_selection: None,
+            code_block_selection: Default::default(),
+            code_block_path_textarea: TextArea::default(),
+            patch_previews: Vec::default(),
+            patch_selection: Default::default(),
+            patch_preview_scroll: Default::default(),
+            sidebar_visible: layout.sidebar,
+            metadata_visible: layout.message_metadata,
+            rename_textarea: TextArea::default(),
+            parameter_selection: 0,
+            model_selection: 0,
+            model_field_selection: None,
+            remote_model_selection: None,
+            model_field_textarea: None,
+            model_delete_confirmation: None,
+            preset_delete_confirmation: None,
+            snippet_delete_confirmation: None,
+            snippet_filter_textarea: TextArea::default(),
+            prompt_history_selection: None,
+            prompt_draft: String::new(),
+            snippet_selection: 0,
+            limit_override: None,
+            context_override: None,
+            mouse_areas: MouseAreas::default(),
         }
     }
+
+    pub fn set_prompt_text(&mut self, text: &str) {
+        self.prompt_textarea.select_all();
+        self.prompt_textarea.cut();
+        self.prompt_textarea.insert_str(text);
+    }
 }
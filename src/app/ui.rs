@@ -1,14 +1,19 @@
-use crate::app::{focus::Scope, state::State};
+use crate::app::{
+    focus::{Scope, Tab},
+    state::State,
+};
 use anyhow::{Context, Result};
 use ratatui::{
-    prelude::{Constraint, Direction, Layout, Stylize},
+    prelude::{Constraint, Direction, Layout, Rect, Stylize},
     widgets::{Block, Paragraph},
     Frame,
 };
+use strum::IntoEnumIterator;
 
 mod chat;
 mod config;
 mod debug;
+mod stats;
 
 pub fn draw(frame: &mut Frame, state: &mut State) -> Result<()> {
     frame.render_widget(
@@ -31,10 +36,21 @@ pub fn draw(frame: &mut Frame, state: &mut State) -> Result<()> {
     draw_title_tabs(frame, state, title_layout).context("draw title tabs")?;
 
     // Status bar
+    let (status_background, status_text) = if state.ui.status_bar_warn {
+        (
+            state.config.ui.colors.background.warn,
+            state.config.ui.colors.text.warn,
+        )
+    } else {
+        (
+            state.config.ui.colors.background.highlight,
+            state.config.ui.colors.text.normal,
+        )
+    };
     frame.render_widget(
         Paragraph::new(state.ui.status_bar_text.as_str())
-            .bg(state.config.ui.colors.background.highlight)
-            .fg(state.config.ui.colors.text.normal),
+            .bg(status_background)
+            .fg(status_text),
         status_bar_layout,
     );
 
@@ -46,8 +62,9 @@ pub fn draw(frame: &mut Frame, state: &mut State) -> Result<()> {
         Scope::Config(config_scope) => {
             config::draw(frame, main_layout, state, config_scope).context("draw config")?;
         }
+        Scope::Stats => stats::draw(frame, main_layout, state).context("draw stats")?,
         Scope::Debug => debug::draw(frame, main_layout, state),
-    };
+    }
     Ok(())
 }
 
@@ -74,11 +91,13 @@ fn draw_title_tabs(
         title_area,
     );
     let selected_tab_index = match state.ui.focus.tab {
-        crate::app::focus::Tab::Chat => 0,
-        crate::app::focus::Tab::Config => 1,
-        crate::app::focus::Tab::Debug => 2,
+        Tab::Chat => 0,
+        Tab::Config => 1,
+        Tab::Stats => 2,
+        Tab::Debug => 3,
     };
-    let tabs_widget = ratatui::widgets::Tabs::new(vec!["Chat", "Config", "Debug"])
+    let tab_titles = ["Chat", "Config", "Stats", "Debug"];
+    let tabs_widget = ratatui::widgets::Tabs::new(tab_titles)
         .style(ratatui::style::Style::default().fg(state.config.ui.colors.frame.inactive))
         .highlight_style(
             ratatui::style::Style::default()
@@ -90,7 +109,22 @@ fn draw_title_tabs(
     let tabs_block = ratatui::widgets::Block::new()
         .borders(ratatui::widgets::Borders::LEFT)
         .fg(state.config.ui.colors.frame.inactive);
+    let tabs_inner_area = tabs_block.inner(tabs_area);
     frame.render_widget(&tabs_block, tabs_area);
-    frame.render_widget(tabs_widget, tabs_block.inner(tabs_area));
+    frame.render_widget(tabs_widget, tabs_inner_area);
+
+    // Tab areas for mouse clicks: each title is padded by a space on both sides and tabs are
+    // separated by a single character divider
+    let mut tab_x = tabs_inner_area.x;
+    state.ui.mouse_areas.tabs.clear();
+    for (tab, title) in Tab::iter().zip(tab_titles) {
+        let width = u16::try_from(title.len())
+            .context("tab title length greater than 16 bits")?
+            .saturating_add(2);
+        let click_area =
+            Rect::new(tab_x, tabs_inner_area.y, width, 1).intersection(tabs_inner_area);
+        state.ui.mouse_areas.tabs.push((click_area, tab));
+        tab_x = tab_x.saturating_add(width).saturating_add(1);
+    }
     Ok(())
 }
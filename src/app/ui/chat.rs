@@ -1,23 +1,124 @@
+use crate::api::Parameter;
 use crate::app::{focus::Chat as ChatFocus, state::State};
+use crate::chat::ContextStrategy;
+use crate::usage::format_cost;
 use anyhow::{Context, Result};
 use ratatui::{
     prelude::{Constraint, Direction, Layout, Line, Rect, Style, Stylize, Text},
     widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState, Wrap},
     Frame,
 };
+use strum::IntoEnumIterator;
 
+mod code_blocks;
 mod history;
 mod new;
+mod parameters;
+mod patches;
+mod sidebar;
+mod snippets;
 
 pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State, scope: ChatFocus) -> Result<()> {
     match scope {
         ChatFocus::New => new::draw(frame, rect, state),
         ChatFocus::History => history::draw(frame, rect, state),
-        _ => draw_conversation(frame, rect, state, scope)?,
-    };
+        ChatFocus::CodeBlocks | ChatFocus::CodeBlockSave => {
+            code_blocks::draw(frame, rect, state, scope)?;
+        }
+        ChatFocus::Patches => patches::draw(frame, rect, state)?,
+        ChatFocus::Snippets => snippets::draw(frame, rect, state)?,
+        ChatFocus::Messages | ChatFocus::Prompt | ChatFocus::Sidebar => {
+            draw_with_sidebar(frame, rect, state, scope)?;
+        }
+        ChatFocus::Parameters => {
+            let parameter_count = u16::try_from(Parameter::iter().count()).unwrap_or(u16::MAX);
+            let layout = Layout::new(
+                Direction::Vertical,
+                [
+                    Constraint::Length(parameter_count.saturating_add(2)),
+                    Constraint::Fill(1),
+                ],
+            )
+            .split(rect);
+            parameters::draw(frame, *layout.first().context("ui index")?, state)?;
+            draw_with_sidebar(frame, *layout.get(1).context("ui index")?, state, scope)?;
+        }
+        ChatFocus::Rename | ChatFocus::SnippetSave | ChatFocus::PromptSearch => {
+            let layout = Layout::new(
+                Direction::Vertical,
+                [Constraint::Length(3), Constraint::Fill(1)],
+            )
+            .split(rect);
+            let title = match scope {
+                ChatFocus::Rename => "Rename conversation (leave empty to generate a title):",
+                ChatFocus::SnippetSave => "Save prompt as snippet:",
+                _ => "Search prompt history (search again for older prompts):",
+            };
+            draw_rename(frame, *layout.first().context("ui index")?, state, title);
+            draw_with_sidebar(frame, *layout.get(1).context("ui index")?, state, scope)?;
+        }
+        ChatFocus::PresetRename => {
+            let layout = Layout::new(
+                Direction::Vertical,
+                [Constraint::Length(3), Constraint::Fill(1)],
+            )
+            .split(rect);
+            draw_rename(
+                frame,
+                *layout.first().context("ui index")?,
+                state,
+                "Rename preset:",
+            );
+            new::draw(frame, *layout.get(1).context("ui index")?, state);
+        }
+    }
     Ok(())
 }
 
+fn draw_with_sidebar(
+    frame: &mut Frame,
+    rect: Rect,
+    state: &mut State,
+    scope: ChatFocus,
+) -> Result<()> {
+    let conversation_rect = if state.ui.sidebar_visible {
+        let layout = Layout::new(
+            Direction::Horizontal,
+            [
+                Constraint::Length(state.config.ui.layout.sidebar_width),
+                Constraint::Fill(1),
+            ],
+        )
+        .split(rect);
+        let sidebar_rect = *layout.first().context("ui index")?;
+        sidebar::draw(frame, sidebar_rect, state, scope == ChatFocus::Sidebar);
+        *layout.get(1).context("ui index")?
+    } else {
+        rect
+    };
+    draw_conversation(frame, conversation_rect, state, scope)
+}
+
+fn draw_rename(frame: &mut Frame, rect: Rect, state: &mut State, title: &str) {
+    let block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(state.config.ui.colors.frame.normal)
+        .title(title)
+        .title_style(state.config.ui.colors.frame.title);
+    state.ui.rename_textarea.set_cursor_line_style(Style::new());
+    state
+        .ui
+        .rename_textarea
+        .set_cursor_style(Style::new().bg(state.config.ui.colors.cursor.normal));
+    state
+        .ui
+        .rename_textarea
+        .set_style(Style::new().fg(state.config.ui.colors.text.normal));
+    let inner = block.inner(rect);
+    frame.render_widget(block, rect);
+    frame.render_widget(&state.ui.rename_textarea, inner);
+}
+
 pub fn draw_conversation(
     frame: &mut Frame,
     rect: Rect,
@@ -34,6 +135,8 @@ pub fn draw_conversation(
     .split(rect);
     let convo_layout = *layout.first().context("ui index")?;
     let prompt_layout = *layout.get(1).context("ui index")?;
+    state.ui.mouse_areas.conversation = convo_layout;
+    state.ui.mouse_areas.prompt = prompt_layout;
     draw_conversation_prompt(frame, prompt_layout, state, scope);
     // Styles
     let is_focused = scope == ChatFocus::Messages;
@@ -59,32 +162,27 @@ pub fn draw_conversation(
                 .into(),
         ])
     } else {
-        let active_conversation = &state.get_active_conversation()?;
-        let mut lines: Vec<Line> = vec!["System"
-            .fg(state.config.ui.colors.text.highlight)
-            .underlined()
-            .into()];
-        for line in active_conversation.system_instructions.lines() {
-            lines.push(line.to_owned().fg(text_color).into());
-        }
-        for message in &state.get_active_conversation()?.messages {
-            lines.push(
-                format!("{}:", message.role)
-                    .to_string()
-                    .fg(state.config.ui.colors.text.highlight)
-                    .into(),
-            );
-            for line in message.content.lines() {
-                lines.push(line.to_owned().fg(text_color).into());
-            }
-        }
-        Text::from_iter(lines)
+        Text::from_iter(get_conversation_lines(state, is_focused)?)
     };
 
+    let conversation = state.get_active_conversation()?;
+    let mut title = match &conversation.title {
+        Some(title) => format!("Conversation: {title}"),
+        None => "Conversation".to_owned(),
+    };
+    if conversation.context_strategy != ContextStrategy::All {
+        title = format!("{title} · {}", conversation.context_strategy);
+    }
+    let total_cost = conversation.total_cost();
+    let title = if total_cost > 0.0 {
+        format!("{title} ({})", format_cost(total_cost))
+    } else {
+        title
+    };
     let block = Block::new()
         .borders(Borders::ALL)
         .fg(state.config.ui.colors.frame.get_active(is_focused))
-        .title("Conversation")
+        .title(title)
         .title_style(Style::new().fg(state.config.ui.colors.frame.title));
     let convo_text = Paragraph::new(convo)
         .wrap(Wrap { trim: false })
@@ -112,6 +210,95 @@ pub fn draw_conversation(
     Ok(())
 }
 
+fn get_conversation_lines(state: &mut State, is_focused: bool) -> Result<Vec<Line<'static>>> {
+    let text_color = state.config.ui.colors.text.get_active(is_focused);
+    let inactive_color = state.config.ui.colors.text.inactive;
+    let context_start = state.get_context_start();
+    let active_conversation = state
+        .conversations
+        .get(state.ui.active_conversation_index)
+        .context("get active conversation")?;
+    let mut lines: Vec<Line> = vec!["System"
+        .fg(state.config.ui.colors.text.highlight)
+        .underlined()
+        .into()];
+    for line in active_conversation.system_instructions.lines() {
+        lines.push(line.to_owned().fg(text_color).into());
+    }
+    if let (ContextStrategy::Summarize { .. }, Some(summary)) = (
+        active_conversation.context_strategy,
+        &active_conversation.summary,
+    ) {
+        lines.push(
+            format!("Summary of the first {} messages:", summary.message_count)
+                .fg(state.config.ui.colors.text.highlight)
+                .into(),
+        );
+        for line in summary.text.lines() {
+            lines.push(line.to_owned().fg(inactive_color).italic().into());
+        }
+    }
+    let text_style = Style::new().fg(text_color);
+    let fence_style = Style::new().fg(state.config.ui.colors.text.inactive);
+    let selected_message_index = state.get_selected_message_index();
+    for (index, message) in active_conversation.messages.iter().enumerate() {
+        if state.ui.metadata_visible && !message.metadata.is_empty() {
+            lines.push(
+                message
+                    .metadata
+                    .to_string()
+                    .fg(state.config.ui.colors.text.inactive)
+                    .italic()
+                    .into(),
+            );
+        }
+        let is_in_context = active_conversation.is_in_context(index, context_start);
+        let is_summarized = active_conversation
+            .summary
+            .as_ref()
+            .is_some_and(|summary| index < summary.message_count)
+            && matches!(
+                active_conversation.context_strategy,
+                ContextStrategy::Summarize { .. }
+            );
+        let marker = if !message.included {
+            " (excluded)"
+        } else if message.pinned {
+            " (pinned)"
+        } else if is_in_context {
+            ""
+        } else if is_summarized {
+            " (summarized)"
+        } else {
+            " (outside context)"
+        };
+        let role_text = format!("{}:{marker}", message.role);
+        let role_line = role_text.fg(if is_in_context {
+            state.config.ui.colors.text.highlight
+        } else {
+            inactive_color
+        });
+        if is_focused && selected_message_index == Some(index) {
+            lines.push(role_line.reversed().into());
+        } else {
+            lines.push(role_line.into());
+        }
+        if is_in_context {
+            lines.extend(state.highlighter.highlight_text(
+                &message.content,
+                &state.config.ui.highlight.theme,
+                text_style,
+                fence_style,
+            ));
+        } else {
+            for line in message.content.lines() {
+                lines.push(line.to_owned().fg(inactive_color).into());
+            }
+        }
+    }
+    Ok(lines)
+}
+
 fn draw_conversation_prompt(frame: &mut Frame, rect: Rect, state: &mut State, scope: ChatFocus) {
     let is_focused = scope == ChatFocus::Prompt;
     let cursor_style = Style::new().bg(state.config.ui.colors.cursor.get_active(is_focused));
@@ -122,10 +309,29 @@ fn draw_conversation_prompt(frame: &mut Frame, rect: Rect, state: &mut State, sc
     state.ui.prompt_textarea.set_cursor_line_style(Style::new());
     state.ui.prompt_textarea.set_cursor_style(cursor_style);
     state.ui.prompt_textarea.set_style(text_style);
+    let prompt_text = state.ui.prompt_textarea.lines().join("\n");
+    let (title, frame_title_style) = match state.get_context_usage(&prompt_text) {
+        Some(usage) => {
+            let mut title = format!("Prompt · ~{} tokens", usage.prompt_tokens);
+            if let Some(context_window) = usage.context_window {
+                title = format!(
+                    "Prompt · ~{} + {} reply / {context_window} tokens",
+                    usage.prompt_tokens, usage.reply_tokens
+                );
+            }
+            let style = if usage.exceeds_window() {
+                Style::new().fg(state.config.ui.colors.text.warn)
+            } else {
+                frame_title_style
+            };
+            (title, style)
+        }
+        None => ("Prompt".to_owned(), frame_title_style),
+    };
     let block = Block::new()
         .borders(Borders::ALL)
         .style(frame_style)
-        .title("Prompt")
+        .title(title)
         .title_style(frame_title_style);
     let inner = block.inner(rect);
     frame.render_widget(block, rect);
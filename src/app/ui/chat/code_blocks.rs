@@ -0,0 +1,104 @@
+use crate::app::{focus::Chat as ChatFocus, state::State};
+use anyhow::{Context, Result};
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Line, Rect, Span, Style, Stylize},
+    widgets::{Block, Borders, List, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+const MAX_LIST_HEIGHT: u16 = 10;
+
+pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State, scope: ChatFocus) -> Result<()> {
+    let code_blocks = state.get_selected_message()?.code_blocks();
+    let is_saving = scope == ChatFocus::CodeBlockSave;
+    let list_height = u16::try_from(code_blocks.len())
+        .unwrap_or(u16::MAX)
+        .min(MAX_LIST_HEIGHT)
+        .saturating_add(2);
+    let layout = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Length(list_height),
+            Constraint::Fill(1),
+            Constraint::Length(if is_saving { 3 } else { 0 }),
+        ],
+    )
+    .split(rect);
+    let list_layout = *layout.first().context("ui index")?;
+    let preview_layout = *layout.get(1).context("ui index")?;
+    let save_layout = *layout.get(2).context("ui index")?;
+
+    // Code block list
+    let list_items = code_blocks.iter().enumerate().map(|(i, code_block)| {
+        let language = code_block.language.as_deref().unwrap_or("text");
+        let line_count = code_block.code.lines().count();
+        let first_line = code_block.code.lines().next().unwrap_or_default().trim();
+        Line::from_iter([
+            Span::styled(
+                format!("{}. {language} ({line_count} lines)", i.saturating_add(1)),
+                Style::new().fg(state.config.ui.colors.text.title),
+            ),
+            " | ".fg(state.config.ui.colors.text.normal),
+            first_line.to_owned().into(),
+        ])
+    });
+    let list = List::new(list_items)
+        .style(state.config.ui.colors.text.normal)
+        .highlight_style(state.config.ui.colors.text.highlight);
+    let mut list_state = ListState::default().with_selected(Some(state.ui.code_block_selection));
+    let list_block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(state.config.ui.colors.frame.get_active(!is_saving))
+        .title("Code blocks in selected message:")
+        .title_style(state.config.ui.colors.frame.title);
+    let list_area = list_block.inner(list_layout);
+    frame.render_widget(list_block, list_layout);
+    frame.render_stateful_widget(list, list_area, &mut list_state);
+
+    // Preview
+    let code_block = code_blocks
+        .get(state.ui.code_block_selection)
+        .context("code block selection out of bounds")?;
+    let preview_lines = state.highlighter.highlight_text(
+        &code_block.to_fenced(),
+        &state.config.ui.highlight.theme,
+        Style::new().fg(state.config.ui.colors.text.normal),
+        Style::new().fg(state.config.ui.colors.text.inactive),
+    );
+    let preview_block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(state.config.ui.colors.frame.inactive)
+        .title("Preview")
+        .title_style(state.config.ui.colors.frame.title);
+    frame.render_widget(
+        Paragraph::new(preview_lines)
+            .wrap(Wrap { trim: false })
+            .block(preview_block),
+        preview_layout,
+    );
+
+    // Save path
+    if is_saving {
+        let save_block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(state.config.ui.colors.frame.normal)
+            .title("Save to file:")
+            .title_style(state.config.ui.colors.frame.title);
+        state
+            .ui
+            .code_block_path_textarea
+            .set_cursor_line_style(Style::new());
+        state
+            .ui
+            .code_block_path_textarea
+            .set_cursor_style(Style::new().bg(state.config.ui.colors.cursor.normal));
+        state
+            .ui
+            .code_block_path_textarea
+            .set_style(Style::new().fg(state.config.ui.colors.text.normal));
+        let save_area = save_block.inner(save_layout);
+        frame.render_widget(save_block, save_layout);
+        frame.render_widget(&state.ui.code_block_path_textarea, save_area);
+    }
+    Ok(())
+}
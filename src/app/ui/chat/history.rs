@@ -8,8 +8,8 @@ use ratatui::{
 pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State) {
     let list_items = state
         .conversations
-        .iter()
-        .map(|c| c.preview(rect.width.into()));
+        .entries()
+        .map(|entry| entry.title_or_preview(rect.width.into()));
     let list = List::new(list_items)
         .style(state.config.ui.colors.text.normal)
         .highlight_style(state.config.ui.colors.text.highlight);
@@ -23,4 +23,6 @@ pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State) {
     let list_area = block.inner(rect);
     frame.render_widget(block, rect);
     frame.render_stateful_widget(list, list_area, &mut list_state);
+    state.ui.mouse_areas.list = list_area;
+    state.ui.mouse_areas.list_offset = list_state.offset();
 }
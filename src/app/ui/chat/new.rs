@@ -37,9 +37,11 @@ pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State) {
     let block = Block::new()
         .borders(Borders::ALL)
         .border_style(state.config.ui.colors.frame.normal)
-        .title("Start new conversation:")
+        .title("Start new conversation (new, copy, rename, edit or clear to manage presets):")
         .title_style(state.config.ui.colors.frame.title);
     let list_area = block.inner(rect);
     frame.render_widget(block, rect);
     frame.render_stateful_widget(list, list_area, &mut list_state);
+    state.ui.mouse_areas.list = list_area;
+    state.ui.mouse_areas.list_offset = list_state.offset();
 }
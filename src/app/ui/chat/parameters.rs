@@ -0,0 +1,59 @@
+use crate::api::Parameter;
+use crate::app::state::State;
+use anyhow::Result;
+use ratatui::{
+    prelude::{Line, Rect, Span, Style, Stylize},
+    widgets::{Block, Borders, List, ListState},
+    Frame,
+};
+use strum::IntoEnumIterator;
+
+const NAME_WIDTH: usize = 20;
+
+pub fn draw(frame: &mut Frame, rect: Rect, state: &State) -> Result<()> {
+    let colors = &state.config.ui.colors;
+    let provider = state.config.provider;
+    let overrides = &state.get_active_conversation()?.parameters;
+    let defaults = crate::api::get_active_model_parameters(state);
+    let capabilities = crate::api::get_active_model_capabilities(state);
+    let list_items = Parameter::iter().map(|parameter| {
+        let name = Span::raw(format!("{:<NAME_WIDTH$}", parameter.to_string()));
+        if !parameter.is_supported(provider) {
+            return Line::from_iter([name, format!("not supported by {provider}").italic()])
+                .fg(colors.text.inactive);
+        }
+        if !capabilities.supports(parameter) {
+            return Line::from_iter([name, "not supported by the model".italic()])
+                .fg(colors.text.inactive);
+        }
+        let (value, source) = if overrides.is_set(parameter) {
+            (
+                overrides.get_display(parameter),
+                "(override)".fg(colors.text.title),
+            )
+        } else {
+            (
+                defaults.get_display(parameter),
+                "(model default)".fg(colors.text.inactive),
+            )
+        };
+        Line::from_iter([
+            name,
+            format!("{:<8} ", value.unwrap_or_else(|| "-".to_owned())).into(),
+            source,
+        ])
+    });
+    let list = List::new(list_items)
+        .style(colors.text.normal)
+        .highlight_style(Style::new().bg(colors.background.highlight));
+    let mut list_state = ListState::default().with_selected(Some(state.ui.parameter_selection));
+    let block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(colors.frame.normal)
+        .title("Conversation parameters (increment/decrement to adjust, clear to reset):")
+        .title_style(colors.frame.title);
+    let list_area = block.inner(rect);
+    frame.render_widget(block, rect);
+    frame.render_stateful_widget(list, list_area, &mut list_state);
+    Ok(())
+}
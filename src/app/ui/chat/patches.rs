@@ -0,0 +1,125 @@
+use crate::app::state::State;
+use crate::chat::{Change, PatchPreview};
+use anyhow::{Context, Result};
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Line, Rect, Span, Style, Stylize},
+    widgets::{Block, Borders, List, ListState, Paragraph, Scrollbar, ScrollbarOrientation},
+    widgets::{ScrollbarState, Wrap},
+    Frame,
+};
+
+const MAX_LIST_HEIGHT: u16 = 10;
+
+pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State) -> Result<()> {
+    let colors = &state.config.ui.colors;
+    let list_height = u16::try_from(state.ui.patch_previews.len())
+        .unwrap_or(u16::MAX)
+        .min(MAX_LIST_HEIGHT)
+        .saturating_add(2);
+    let layout = Layout::new(
+        Direction::Vertical,
+        [Constraint::Length(list_height), Constraint::Fill(1)],
+    )
+    .split(rect);
+    let list_layout = *layout.first().context("ui index")?;
+    let preview_layout = *layout.get(1).context("ui index")?;
+
+    // Patch list
+    let list_items = state.ui.patch_previews.iter().map(|preview| {
+        let kind = match (&preview.patch.change, preview.is_new_file) {
+            (_, true) => "new file",
+            (Change::Diff(_), false) => "diff",
+            (Change::Replace(_), false) => "replace",
+            (Change::Delete, false) => "delete",
+        };
+        let (status, status_color) = match (&preview.result, preview.applied) {
+            (_, true) => ("applied", colors.text.title),
+            (Ok(_), false) => ("ready", colors.text.normal),
+            (Err(_), false) => ("cannot apply", colors.text.warn),
+        };
+        Line::from_iter([
+            Span::raw(preview.patch.path.display().to_string()),
+            format!(" ({kind}) ").fg(colors.text.inactive),
+            status.fg(status_color),
+        ])
+    });
+    let list = List::new(list_items)
+        .style(colors.text.normal)
+        .highlight_style(Style::new().bg(colors.background.highlight));
+    let mut list_state = ListState::default().with_selected(Some(state.ui.patch_selection));
+    let list_block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(colors.frame.normal)
+        .title("Apply changes to local files:")
+        .title_style(colors.frame.title);
+    let list_area = list_block.inner(list_layout);
+    frame.render_widget(list_block, list_layout);
+    frame.render_stateful_widget(list, list_area, &mut list_state);
+
+    // Preview
+    let preview = state
+        .ui
+        .patch_previews
+        .get(state.ui.patch_selection)
+        .context("patch selection out of bounds")?;
+    let preview_lines = get_preview_lines(preview, state);
+    let preview_title = if preview.applied {
+        "Preview (applied)"
+    } else {
+        "Preview (confirm to apply)"
+    };
+    let preview_block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(colors.frame.normal)
+        .title(preview_title)
+        .title_style(colors.frame.title);
+    let preview_text = Paragraph::new(preview_lines)
+        .wrap(Wrap { trim: false })
+        .block(preview_block);
+    let line_count = preview_text.line_count(preview_layout.width.saturating_sub(2));
+    let max_scroll = u16::try_from(line_count)
+        .unwrap_or(u16::MAX)
+        .saturating_sub(3);
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .style(Style::new().fg(colors.widget.normal));
+    state.ui.patch_preview_scroll = state.ui.patch_preview_scroll.min(max_scroll);
+    let mut scrollbar_state =
+        ScrollbarState::new(max_scroll.into()).position(state.ui.patch_preview_scroll.into());
+    let scrollbar_area = preview_layout.inner(ratatui::layout::Margin {
+        horizontal: 0,
+        vertical: 1,
+    });
+    frame.render_widget(
+        preview_text.scroll((state.ui.patch_preview_scroll, 0)),
+        preview_layout,
+    );
+    frame.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
+    Ok(())
+}
+
+fn get_preview_lines(preview: &PatchPreview, state: &State) -> Vec<Line<'static>> {
+    let colors = &state.config.ui.colors;
+    match &preview.result {
+        Ok((_, diff)) if diff.lines().count() <= 2 => {
+            vec!["No changes to local file".fg(colors.text.inactive).into()]
+        }
+        Ok((_, diff)) => diff
+            .lines()
+            .map(|line| {
+                let color = if line.starts_with("+++") || line.starts_with("---") {
+                    colors.text.title
+                } else if line.starts_with('+') {
+                    colors.text.highlight
+                } else if line.starts_with('-') {
+                    colors.text.warn
+                } else if line.starts_with("@@") {
+                    colors.text.inactive
+                } else {
+                    colors.text.normal
+                };
+                line.to_owned().fg(color).into()
+            })
+            .collect(),
+        Err(error) => vec![error.clone().fg(colors.text.warn).into()],
+    }
+}
@@ -0,0 +1,49 @@
+use crate::app::state::State;
+use ratatui::{
+    prelude::{Line, Rect, Style, Stylize, Text},
+    widgets::{Block, Borders, List, ListItem, ListState},
+    Frame,
+};
+
+pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State, is_focused: bool) {
+    let colors = &state.config.ui.colors;
+    let block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(colors.frame.get_active(is_focused))
+        .title("Conversations")
+        .title_style(colors.frame.title);
+    let list_area = block.inner(rect);
+    let list_items = state.conversations.entries().map(|entry| {
+        let date = entry.created_at.map_or_else(
+            || "-".to_owned(),
+            |created_at| {
+                created_at
+                    .with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            },
+        );
+        let details = match &entry.last_model {
+            Some(model) => format!("{date} · {model}"),
+            None => date,
+        };
+        // Each item must be exactly SIDEBAR_ITEM_HEIGHT lines for mouse handling
+        ListItem::new(Text::from_iter([
+            Line::from(entry.title_or_preview(list_area.width.into())),
+            details.fg(colors.text.inactive).into(),
+        ]))
+    });
+    let list = List::new(list_items)
+        .style(colors.text.get_active(is_focused))
+        .highlight_style(
+            Style::new()
+                .fg(colors.text.highlight)
+                .bg(colors.background.highlight),
+        );
+    let mut list_state =
+        ListState::default().with_selected(Some(state.ui.active_conversation_index));
+    frame.render_widget(block, rect);
+    frame.render_stateful_widget(list, list_area, &mut list_state);
+    state.ui.mouse_areas.sidebar = list_area;
+    state.ui.mouse_areas.sidebar_offset = list_state.offset();
+}
@@ -0,0 +1,101 @@
+use crate::app::state::State;
+use anyhow::{Context, Result};
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Line, Rect, Span, Style, Stylize},
+    widgets::{Block, Borders, List, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+const MAX_LIST_HEIGHT: u16 = 10;
+
+pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State) -> Result<()> {
+    let snippets = state.get_filtered_snippets();
+    let list_height = u16::try_from(snippets.len())
+        .unwrap_or(u16::MAX)
+        .clamp(1, MAX_LIST_HEIGHT)
+        .saturating_add(2);
+    let layout = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Length(3),
+            Constraint::Length(list_height),
+            Constraint::Fill(1),
+        ],
+    )
+    .split(rect);
+    let filter_layout = *layout.first().context("ui index")?;
+    let list_layout = *layout.get(1).context("ui index")?;
+    let preview_layout = *layout.get(2).context("ui index")?;
+
+    // Snippet list
+    let list_items = snippets.iter().map(|snippet| {
+        Line::from_iter([
+            Span::styled(
+                snippet.name.clone(),
+                Style::new().fg(state.config.ui.colors.text.title),
+            ),
+            " | ".fg(state.config.ui.colors.text.normal),
+            snippet.preview(list_layout.width.into()).into(),
+        ])
+    });
+    let list = List::new(list_items)
+        .style(state.config.ui.colors.text.normal)
+        .highlight_style(state.config.ui.colors.text.highlight);
+    let mut list_state = ListState::default().with_selected(Some(state.ui.snippet_selection));
+    let list_title = if state.snippets.is_empty() {
+        "No snippets (save a prompt as a snippet from the prompt):"
+    } else {
+        "Insert snippet (edit or clear to manage snippets):"
+    };
+    let list_block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(state.config.ui.colors.frame.normal)
+        .title(list_title)
+        .title_style(state.config.ui.colors.frame.title);
+    let list_area = list_block.inner(list_layout);
+    frame.render_widget(list_block, list_layout);
+    frame.render_stateful_widget(list, list_area, &mut list_state);
+
+    // Preview
+    let preview_text = snippets
+        .get(state.ui.snippet_selection)
+        .map(|snippet| snippet.text.clone())
+        .unwrap_or_default();
+    let preview_block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(state.config.ui.colors.frame.inactive)
+        .title("Preview")
+        .title_style(state.config.ui.colors.frame.title);
+    frame.render_widget(
+        Paragraph::new(preview_text)
+            .style(state.config.ui.colors.text.normal)
+            .wrap(Wrap { trim: false })
+            .block(preview_block),
+        preview_layout,
+    );
+    state.ui.mouse_areas.list = list_area;
+    state.ui.mouse_areas.list_offset = list_state.offset();
+
+    // Filter
+    let filter_block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(state.config.ui.colors.frame.normal)
+        .title("Search snippets:")
+        .title_style(state.config.ui.colors.frame.title);
+    state
+        .ui
+        .snippet_filter_textarea
+        .set_cursor_line_style(Style::new());
+    state
+        .ui
+        .snippet_filter_textarea
+        .set_cursor_style(Style::new().bg(state.config.ui.colors.cursor.normal));
+    state
+        .ui
+        .snippet_filter_textarea
+        .set_style(Style::new().fg(state.config.ui.colors.text.normal));
+    let filter_area = filter_block.inner(filter_layout);
+    frame.render_widget(filter_block, filter_layout);
+    frame.render_widget(&state.ui.snippet_filter_textarea, filter_area);
+    Ok(())
+}
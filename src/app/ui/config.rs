@@ -1,12 +1,18 @@
+use crate::api::{Parameter, Provider, RemoteModel};
 use crate::app::focus::Config as ConfigFocus;
 use crate::app::state::State;
+use crate::config::{ModelEntry, ModelField};
+use crate::usage::format_cost;
 use anyhow::{Context, Result};
+use chrono::Datelike;
 use ratatui::{
-    prelude::{Constraint, Direction, Layout, Rect, Style},
-    widgets::{Block, Borders, Paragraph},
+    prelude::{Constraint, Direction, Layout, Line, Rect, Span, Style, Stylize},
+    widgets::{Block, Borders, Clear, List, ListState, Paragraph, Row, Table, TableState},
     Frame,
 };
 
+const FIELD_NAME_WIDTH: usize = 20;
+
 pub fn draw(
     frame: &mut Frame,
     rect: Rect,
@@ -15,7 +21,7 @@ pub fn draw(
 ) -> Result<()> {
     let outer_layout = Layout::new(
         Direction::Vertical,
-        [Constraint::Length(3), Constraint::Fill(1)],
+        [Constraint::Length(4), Constraint::Fill(1)],
     )
     .split(rect);
     let top_layout = outer_layout.first().context("ui index")?;
@@ -31,27 +37,233 @@ pub fn draw(
 
     frame.render_widget(&config_block, *top_layout);
     frame.render_widget(
-        Paragraph::new(format!(
-            "Config file: {}",
-            state.paths.get_config_file().display()
-        ))
+        Paragraph::new(vec![
+            Line::from(format!(
+                "Config file: {}",
+                state.paths.get_config_file().display()
+            )),
+            Line::from(get_spending_summary(state)),
+        ])
         .style(text_style),
         config_block.inner(*top_layout),
     );
-    let (title, config_details) = match config_scope {
-        ConfigFocus::OpenAi => ("OpenAI", format!("{:#?}", state.models.openai)),
-        ConfigFocus::Anthropic => ("Anthropic", format!("{:#?}", state.models.anthropic)),
-    };
+    let provider = config_scope.provider();
+    let editor_layout = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Fill(3), Constraint::Fill(2)],
+    )
+    .split(*bottom_layout);
+    let mut models_layout = *editor_layout.first().context("ui index")?;
+    let fields_layout = *editor_layout.get(1).context("ui index")?;
+    let remote_models = state
+        .remote_models
+        .get(&provider)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let unconfigured = state.models.get_unconfigured(provider, remote_models);
+    if !unconfigured.is_empty() {
+        let models_split = Layout::new(
+            Direction::Vertical,
+            [Constraint::Fill(2), Constraint::Fill(1)],
+        )
+        .split(models_layout);
+        models_layout = *models_split.first().context("ui index")?;
+        let remote_layout = *models_split.get(1).context("ui index")?;
+        draw_unconfigured(frame, remote_layout, state, &unconfigured);
+    }
+    match provider {
+        Provider::OpenAi => {
+            draw_models(frame, models_layout, state, provider, &state.models.openai);
+            draw_fields(frame, fields_layout, state, provider, &state.models.openai);
+        }
+        Provider::Anthropic => {
+            draw_models(
+                frame,
+                models_layout,
+                state,
+                provider,
+                &state.models.anthropic,
+            );
+            draw_fields(
+                frame,
+                fields_layout,
+                state,
+                provider,
+                &state.models.anthropic,
+            );
+        }
+    }
+    draw_field_edit(frame, fields_layout, state);
+    Ok(())
+}
 
-    let config_block = Block::new()
+fn get_block<'a>(state: &State, title: String, focused: bool) -> Block<'a> {
+    let colors = &state.config.ui.colors;
+    Block::new()
         .borders(Borders::ALL)
-        .border_style(state.config.ui.colors.frame.normal)
-        .title(format!("{title} Configuration"))
-        .title_style(state.config.ui.colors.frame.title);
-    frame.render_widget(&config_block, *bottom_layout);
-    frame.render_widget(
-        Paragraph::new(config_details).style(text_style),
-        config_block.inner(*bottom_layout),
+        .border_style(colors.frame.get_active(focused))
+        .title(title)
+        .title_style(colors.frame.title)
+}
+
+fn draw_models<M: ModelEntry>(
+    frame: &mut Frame,
+    rect: Rect,
+    state: &State,
+    provider: Provider,
+    models: &[M],
+) {
+    let colors = &state.config.ui.colors;
+    let columns = [
+        ModelField::Name,
+        ModelField::Id,
+        ModelField::Parameter(Parameter::MaxTokens),
+        ModelField::Parameter(Parameter::Temperature),
+        ModelField::ContextWindow,
+    ];
+    let header = Row::new(columns.map(|field| field.to_string()))
+        .style(Style::new().fg(colors.text.title).bold());
+    let rows = models.iter().map(|model| {
+        Row::new(columns.map(|field| model.get_field(field).unwrap_or_else(|| "-".to_owned())))
+    });
+    let widths = [
+        Constraint::Fill(2),
+        Constraint::Fill(2),
+        Constraint::Length(12),
+        Constraint::Length(12),
+        Constraint::Length(14),
+    ];
+    let title = format!("{} models", provider_title(provider));
+    let table = Table::new(rows, widths)
+        .header(header)
+        .style(Style::new().fg(colors.text.normal))
+        .row_highlight_style(Style::new().bg(colors.background.highlight))
+        .block(get_block(
+            state,
+            title,
+            state.ui.model_field_selection.is_none() && state.ui.remote_model_selection.is_none(),
+        ));
+    let mut table_state = TableState::default().with_selected(
+        (!models.is_empty() && state.ui.remote_model_selection.is_none())
+            .then_some(state.ui.model_selection),
     );
-    Ok(())
+    frame.render_stateful_widget(table, rect, &mut table_state);
+}
+
+fn draw_fields<M: ModelEntry>(
+    frame: &mut Frame,
+    rect: Rect,
+    state: &State,
+    provider: Provider,
+    models: &[M],
+) {
+    let colors = &state.config.ui.colors;
+    let block = get_block(
+        state,
+        "Model fields".to_owned(),
+        state.ui.model_field_selection.is_some(),
+    );
+    let Some(model) = models.get(state.ui.model_selection) else {
+        frame.render_widget(
+            Paragraph::new("No models configured.")
+                .fg(colors.text.inactive)
+                .block(block),
+            rect,
+        );
+        return;
+    };
+    let list_items = ModelField::all(provider).into_iter().map(|field| {
+        let value = model.get_field(field).unwrap_or_else(|| {
+            match field {
+                ModelField::Pricing => "no pricing (costs are not tracked)",
+                ModelField::ContextWindow => "unknown (no context warnings)",
+                _ => "-",
+            }
+            .to_owned()
+        });
+        let value_style = if field.is_adjustable() || field.is_text() {
+            Style::new().fg(colors.text.normal)
+        } else {
+            Style::new().fg(colors.text.inactive)
+        };
+        Line::from_iter([
+            Span::raw(format!("{:<FIELD_NAME_WIDTH$}", field.to_string())),
+            Span::styled(value, value_style),
+        ])
+    });
+    let list = List::new(list_items)
+        .style(colors.text.normal)
+        .highlight_style(Style::new().bg(colors.background.highlight));
+    let mut list_state = ListState::default().with_selected(state.ui.model_field_selection);
+    frame.render_stateful_widget(list.block(block), rect, &mut list_state);
+}
+
+fn draw_unconfigured(frame: &mut Frame, rect: Rect, state: &State, unconfigured: &[&RemoteModel]) {
+    let colors = &state.config.ui.colors;
+    let list_items = unconfigured.iter().map(|remote_model| {
+        let id = if remote_model.id == remote_model.name {
+            Span::default()
+        } else {
+            Span::styled(
+                format!(" ({})", remote_model.id),
+                Style::new().fg(colors.text.inactive),
+            )
+        };
+        Line::from_iter([Span::raw(remote_model.name.clone()), id])
+    });
+    let list = List::new(list_items)
+        .style(colors.text.normal)
+        .highlight_style(Style::new().bg(colors.background.highlight))
+        .block(get_block(
+            state,
+            "Available from the API, not configured (select to add)".to_owned(),
+            state.ui.remote_model_selection.is_some(),
+        ));
+    let mut list_state = ListState::default().with_selected(state.ui.remote_model_selection);
+    frame.render_stateful_widget(list, rect, &mut list_state);
+}
+
+fn draw_field_edit(frame: &mut Frame, rect: Rect, state: &mut State) {
+    let colors = state.config.ui.colors.clone();
+    let Some(textarea) = state.ui.model_field_textarea.as_mut() else {
+        return;
+    };
+    let edit_rect = Rect {
+        y: rect.bottom().saturating_sub(3),
+        height: rect.height.min(3),
+        ..rect
+    };
+    let block = Block::new()
+        .borders(Borders::ALL)
+        .border_style(colors.frame.normal)
+        .title("Edit field (select to save, cancel to discard):")
+        .title_style(colors.frame.title);
+    textarea.set_cursor_line_style(Style::new());
+    textarea.set_cursor_style(Style::new().bg(colors.cursor.normal));
+    textarea.set_style(Style::new().fg(colors.text.normal));
+    let inner = block.inner(edit_rect);
+    frame.render_widget(Clear, edit_rect);
+    frame.render_widget(block, edit_rect);
+    frame.render_widget(&*textarea, inner);
+}
+
+fn provider_title(provider: Provider) -> &'static str {
+    match provider {
+        Provider::OpenAi => "OpenAI",
+        Provider::Anthropic => "Anthropic",
+    }
+}
+
+fn get_spending_summary(state: &State) -> String {
+    let today = chrono::Local::now().date_naive();
+    let month_start = today.with_day(1).unwrap_or(today);
+    let today_totals = state.ledger.totals_since(today, None);
+    let month_totals = state.ledger.totals_since(month_start, None);
+    format!(
+        "Spent today: {} ({} requests) · this month: {} ({} requests)",
+        format_cost(today_totals.cost),
+        today_totals.requests,
+        format_cost(month_totals.cost),
+        month_totals.requests,
+    )
 }
@@ -31,6 +31,7 @@ pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State) {
         vertical: 1,
     });
 
+    state.ui.mouse_areas.debug = rect;
     frame.render_widget(debug_text, rect);
     frame.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
 }
@@ -0,0 +1,203 @@
+use crate::app::state::State;
+use crate::chat::Stats;
+use crate::usage::format_cost;
+use anyhow::{Context, Result};
+use ratatui::{
+    prelude::{Constraint, Direction, Layout, Line, Rect, Style, Stylize},
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, List, Paragraph, Row, Table},
+    Frame,
+};
+
+const BAR_WIDTH: u16 = 5;
+const BAR_GAP: u16 = 1;
+const INSTRUCTIONS_PREVIEW_LENGTH: usize = 50;
+
+pub fn draw(frame: &mut Frame, rect: Rect, state: &mut State) -> Result<()> {
+    state.update_stats();
+    let state = &*state;
+    let usage_stats = state.stats.as_ref().context("stats not computed")?;
+    let layout = Layout::new(
+        Direction::Vertical,
+        [
+            Constraint::Length(3),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ],
+    )
+    .split(rect);
+    let summary_layout = *layout.first().context("ui index")?;
+    let middle_layout = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Fill(3), Constraint::Fill(2)],
+    )
+    .split(*layout.get(1).context("ui index")?);
+    let models_layout = *middle_layout.first().context("ui index")?;
+    let spending_layout = *middle_layout.get(1).context("ui index")?;
+    let bottom_layout = Layout::new(
+        Direction::Horizontal,
+        [Constraint::Fill(2), Constraint::Fill(1)],
+    )
+    .split(*layout.get(2).context("ui index")?);
+    let requests_layout = *bottom_layout.first().context("ui index")?;
+    let instructions_layout = *bottom_layout.get(1).context("ui index")?;
+
+    let request_count: u64 = usage_stats.requests_per_day.values().sum();
+    let summary = format!(
+        "{} conversations · {} messages · {request_count} requests",
+        usage_stats.conversations, usage_stats.messages
+    );
+    frame.render_widget(
+        Paragraph::new(summary)
+            .fg(state.config.ui.colors.text.normal)
+            .block(get_block(state, "Saved conversations")),
+        summary_layout,
+    );
+    draw_models(frame, models_layout, state, usage_stats);
+    draw_spending(frame, spending_layout, state);
+    draw_requests_per_day(frame, requests_layout, state, usage_stats);
+    draw_system_instructions(frame, instructions_layout, state, usage_stats);
+    Ok(())
+}
+
+fn get_block<'a>(state: &State, title: &'a str) -> Block<'a> {
+    Block::new()
+        .borders(Borders::ALL)
+        .border_style(state.config.ui.colors.frame.normal)
+        .title(title)
+        .title_style(state.config.ui.colors.frame.title)
+}
+
+fn draw_models(frame: &mut Frame, rect: Rect, state: &State, usage_stats: &Stats) {
+    let colors = &state.config.ui.colors;
+    let header = Row::new([
+        "Provider",
+        "Model",
+        "Messages",
+        "Prompt tokens",
+        "Completion tokens",
+        "Avg latency",
+    ])
+    .style(Style::new().fg(colors.text.title).bold());
+    let rows = usage_stats
+        .models
+        .iter()
+        .map(|((provider, model), model_stats)| {
+            #[allow(clippy::cast_precision_loss)]
+            let latency = model_stats.average_latency_ms().map_or_else(
+                || "-".to_owned(),
+                |ms| format!("{:.1}s", ms as f64 / 1000.0),
+            );
+            Row::new([
+                provider.clone(),
+                model.clone(),
+                model_stats.messages.to_string(),
+                model_stats.usage.prompt.to_string(),
+                model_stats.usage.completion.to_string(),
+                latency,
+            ])
+        });
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Fill(1),
+        Constraint::Length(10),
+        Constraint::Length(15),
+        Constraint::Length(18),
+        Constraint::Length(12),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .style(Style::new().fg(colors.text.normal))
+        .block(get_block(state, "Models"));
+    frame.render_widget(table, rect);
+}
+
+fn draw_spending(frame: &mut Frame, rect: Rect, state: &State) {
+    let colors = &state.config.ui.colors;
+    let header = Row::new(["Date", "Model", "Requests", "Tokens", "Cost"])
+        .style(Style::new().fg(colors.text.title).bold());
+    let totals = state.ledger.totals_by_day_and_model();
+    // Newest days first
+    let rows = totals.iter().rev().map(|((date, model_id), totals)| {
+        Row::new([
+            date.format("%Y-%m-%d").to_string(),
+            model_id.clone(),
+            totals.requests.to_string(),
+            totals.usage.total.to_string(),
+            format_cost(totals.cost),
+        ])
+    });
+    let widths = [
+        Constraint::Length(10),
+        Constraint::Fill(1),
+        Constraint::Length(8),
+        Constraint::Length(10),
+        Constraint::Length(10),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .style(Style::new().fg(colors.text.normal))
+        .block(get_block(state, "Spending by day (from the ledger)"));
+    frame.render_widget(table, rect);
+}
+
+fn draw_requests_per_day(frame: &mut Frame, rect: Rect, state: &State, usage_stats: &Stats) {
+    let colors = &state.config.ui.colors;
+    let block = get_block(state, "Requests per day");
+    let inner_width = block.inner(rect).width;
+    let day_count = (inner_width.saturating_add(BAR_GAP) / (BAR_WIDTH + BAR_GAP)).max(1);
+    let today = chrono::Local::now().date_naive();
+    let bars: Vec<Bar> = (0..day_count)
+        .rev()
+        .filter_map(|days_ago| today.checked_sub_days(chrono::Days::new(days_ago.into())))
+        .map(|date| {
+            let requests = usage_stats
+                .requests_per_day
+                .get(&date)
+                .copied()
+                .unwrap_or_default();
+            Bar::default()
+                .value(requests)
+                .label(Line::from(date.format("%m-%d").to_string()))
+                .style(Style::new().fg(colors.widget.normal))
+                .value_style(
+                    Style::new()
+                        .fg(colors.text.highlight)
+                        .bg(colors.widget.normal),
+                )
+        })
+        .collect();
+    let chart = BarChart::default()
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(BAR_WIDTH)
+        .bar_gap(BAR_GAP)
+        .label_style(Style::new().fg(colors.text.inactive))
+        .block(block);
+    frame.render_widget(chart, rect);
+}
+
+fn draw_system_instructions(frame: &mut Frame, rect: Rect, state: &State, usage_stats: &Stats) {
+    let colors = &state.config.ui.colors;
+    let items = usage_stats
+        .system_instructions
+        .iter()
+        .map(|(instructions, count)| {
+            let name = state
+                .config
+                .system
+                .instructions
+                .iter()
+                .find(|preset| preset.message == *instructions)
+                .map_or_else(
+                    || crate::preview(instructions, INSTRUCTIONS_PREVIEW_LENGTH),
+                    |preset| preset.name.clone(),
+                );
+            Line::from_iter([
+                format!("{count:>4} ").fg(colors.text.title),
+                name.fg(colors.text.normal),
+            ])
+        });
+    frame.render_widget(
+        List::new(items).block(get_block(state, "System instructions")),
+        rect,
+    );
+}
@@ -1,5 +1,23 @@
+use crate::api::{Parameters, Provider, TokenUsage};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+mod code_block;
+mod context;
+mod patch;
+mod prompt_history;
+mod stats;
+mod store;
+mod template;
+
+pub use code_block::{parse_code_blocks, CodeBlock};
+pub use context::{ContextStrategy, Summary};
+pub use patch::{find_file_patches, Change, FilePatch, PatchPreview};
+pub use prompt_history::PromptHistory;
+pub use stats::{ModelStats, Stats};
+pub use store::{ConversationEntry, ConversationStore};
+pub use template::expand_template;
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum Role {
@@ -20,6 +38,18 @@ impl std::fmt::Display for Role {
 pub struct Message {
     pub role: Role,
     pub content: String,
+    #[serde(default)]
+    pub metadata: Metadata,
+    /// Excluded messages are kept in the history but not sent to the model.
+    #[serde(default = "default_included")]
+    pub included: bool,
+    /// Pinned messages are always sent to the model, regardless of the context strategy.
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+fn default_included() -> bool {
+    true
 }
 
 impl Message {
@@ -28,8 +58,35 @@ impl Message {
         Self {
             role: Role::User,
             content,
+            metadata: Metadata {
+                created_at: Some(Utc::now()),
+                ..Default::default()
+            },
+            included: true,
+            pinned: false,
         }
     }
+
+    #[must_use]
+    pub fn new_assistant_message(model_name: String, content: String, metadata: Metadata) -> Self {
+        Self {
+            role: Role::Assistant(model_name),
+            content,
+            metadata,
+            included: true,
+            pinned: false,
+        }
+    }
+
+    #[must_use]
+    pub fn code_blocks(&self) -> Vec<CodeBlock> {
+        parse_code_blocks(&self.content)
+    }
+
+    #[must_use]
+    pub fn file_patches(&self) -> Vec<FilePatch> {
+        find_file_patches(&self.content)
+    }
 }
 
 impl std::fmt::Display for Message {
@@ -38,10 +95,83 @@ impl std::fmt::Display for Message {
     }
 }
 
+/// Details of how a message was created.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct Metadata {
+    pub created_at: Option<DateTime<Utc>>,
+    pub provider: Option<Provider>,
+    pub model_id: Option<String>,
+    pub parameters: Option<Parameters>,
+    pub usage: Option<TokenUsage>,
+    pub latency_ms: Option<u64>,
+    /// Cost in USD, missing if the model has no configured pricing.
+    pub cost: Option<f64>,
+}
+
+impl Metadata {
+    /// Messages saved by older versions have no metadata.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.created_at.is_none()
+            && self.provider.is_none()
+            && self.model_id.is_none()
+            && self.parameters.is_none()
+            && self.usage.is_none()
+            && self.latency_ms.is_none()
+            && self.cost.is_none()
+    }
+}
+
+impl std::fmt::Display for Metadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(created_at) = self.created_at {
+            let local_time = created_at.with_timezone(&chrono::Local);
+            parts.push(local_time.format("%Y-%m-%d %H:%M:%S").to_string());
+        }
+        match (self.provider, &self.model_id) {
+            (Some(provider), Some(model_id)) => parts.push(format!("{provider}/{model_id}")),
+            (None, Some(model_id)) => parts.push(model_id.clone()),
+            (Some(provider), None) => parts.push(provider.to_string()),
+            (None, None) => (),
+        }
+        if let Some(parameters) = &self.parameters {
+            parts.push(parameters.to_string());
+        }
+        if let Some(usage) = &self.usage {
+            parts.push(usage.to_string());
+        }
+        if let Some(latency_ms) = self.latency_ms {
+            #[allow(clippy::cast_precision_loss)]
+            let latency_seconds = latency_ms as f64 / 1000.0;
+            parts.push(format!("{latency_seconds:.1}s"));
+        }
+        if let Some(cost) = self.cost {
+            parts.push(crate::usage::format_cost(cost));
+        }
+        write!(f, "{}", parts.join(" · "))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conversation {
     pub system_instructions: String,
     pub messages: Vec<Message>,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub context_strategy: ContextStrategy,
+    #[serde(default)]
+    pub summary: Option<Summary>,
+    /// Overrides of the model parameters.
+    #[serde(default)]
+    pub parameters: Parameters,
+    /// Unsent text of the prompt.
+    #[serde(default)]
+    pub draft: String,
 }
 
 impl Conversation {
@@ -50,6 +180,12 @@ impl Conversation {
         Self {
             system_instructions,
             messages: Vec::new(),
+            created_at: Some(Utc::now()),
+            title: None,
+            context_strategy: ContextStrategy::default(),
+            summary: None,
+            parameters: Parameters::default(),
+            draft: String::new(),
         }
     }
 
@@ -62,19 +198,49 @@ impl Conversation {
         self.messages.is_empty()
     }
 
+    /// Total cost of all messages with a known cost.
+    #[must_use]
+    pub fn total_cost(&self) -> f64 {
+        self.messages
+            .iter()
+            .filter_map(|message| message.metadata.cost)
+            .sum()
+    }
+
+    /// Get the model of the last assistant message.
+    #[must_use]
+    pub fn last_model(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .rev()
+            .find_map(|message| match &message.role {
+                Role::Assistant(model) => Some(model.as_str()),
+                Role::User => None,
+            })
+    }
+
+    /// Preview of the first user message.
     #[must_use]
     pub fn preview(&self, length: usize) -> String {
-        if let Some(first_message) = self.messages.get(1) {
-            first_message
-                .content
-                .chars()
-                .take(length)
-                .map(|char| if char == '\n' { ' ' } else { char })
-                .collect()
+        if let Some(first_message) = self
+            .messages
+            .iter()
+            .find(|message| matches!(message.role, Role::User))
+        {
+            crate::preview(&first_message.content, length)
         } else {
             "<EMPTY>".to_string()
         }
     }
+
+    /// The title, or a preview of the first user message if there is no title.
+    #[must_use]
+    pub fn title_or_preview(&self, length: usize) -> String {
+        match &self.title {
+            Some(title) => title.chars().take(length).collect(),
+            None => self.preview(length),
+        }
+    }
 }
 
 impl std::fmt::Display for Conversation {
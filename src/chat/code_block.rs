@@ -0,0 +1,151 @@
+/// A fenced code block (delimited by backticks or tildes) found in a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    pub language: Option<String>,
+    pub code: String,
+    /// Line index of the opening fence.
+    pub fence_start: usize,
+    /// Line index of the closing fence, missing if the block was never closed.
+    pub fence_end: Option<usize>,
+}
+
+impl CodeBlock {
+    /// Check if the line at the given index is part of the block's code (excluding fences).
+    #[must_use]
+    pub fn contains_code_line(&self, line_index: usize) -> bool {
+        line_index > self.fence_start && self.fence_end.is_none_or(|end| line_index < end)
+    }
+
+    #[must_use]
+    pub fn is_fence_line(&self, line_index: usize) -> bool {
+        line_index == self.fence_start || self.fence_end == Some(line_index)
+    }
+
+    /// The block fenced with more backticks than any run of backticks in the code, so the code
+    /// cannot close the fence.
+    #[must_use]
+    pub fn to_fenced(&self) -> String {
+        let longest_run = self
+            .code
+            .split(|char| char != '`')
+            .map(str::len)
+            .max()
+            .unwrap_or_default();
+        let fence = "`".repeat(longest_run.max(2) + 1);
+        let newline = if self.code.is_empty() || self.code.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        format!(
+            "{fence}{}\n{}{newline}{fence}\n",
+            self.language.as_deref().unwrap_or_default(),
+            self.code
+        )
+    }
+}
+
+struct Fence {
+    marker: char,
+    length: usize,
+}
+
+impl Fence {
+    fn parse(line: &str) -> Option<(Self, &str)> {
+        let indent = line.len() - line.trim_start_matches(' ').len();
+        if indent > 3 {
+            return None;
+        }
+        let trimmed = &line[indent..];
+        let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+        let length = trimmed.chars().take_while(|c| *c == marker).count();
+        if length < 3 {
+            return None;
+        }
+        let info = trimmed[length..].trim();
+        if marker == '`' && info.contains('`') {
+            return None;
+        }
+        Some((Self { marker, length }, info))
+    }
+
+    fn is_closed_by(&self, line: &str) -> bool {
+        Self::parse(line).is_some_and(|(fence, info)| {
+            fence.marker == self.marker && fence.length >= self.length && info.is_empty()
+        })
+    }
+}
+
+/// Find all fenced code blocks in the text.
+#[must_use]
+pub fn parse_code_blocks(text: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(Fence, CodeBlock)> = None;
+    for (index, line) in text.lines().enumerate() {
+        match open.take() {
+            Some((fence, mut block)) => {
+                if fence.is_closed_by(line) {
+                    block.fence_end = Some(index);
+                    blocks.push(block);
+                } else {
+                    block.code.push_str(line);
+                    block.code.push('\n');
+                    open = Some((fence, block));
+                }
+            }
+            None => {
+                if let Some((fence, info)) = Fence::parse(line) {
+                    let language = info.split_whitespace().next().map(str::to_owned);
+                    let block = CodeBlock {
+                        language,
+                        code: String::new(),
+                        fence_start: index,
+                        fence_end: None,
+                    };
+                    open = Some((fence, block));
+                }
+            }
+        }
+    }
+    if let Some((_, block)) = open {
+        blocks.push(block);
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod code_block_tests {
+    use super::parse_code_blocks;
+
+    #[test]
+    fn fenced_blocks() {
+        let text = "intro\n```rust ignore\nfn main() {}\n```\ntext\n~~~~\n```\nplain\n~~~~\n";
+        let blocks = parse_code_blocks(text);
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].language.as_deref(), Some("rust"));
+        assert_eq!(blocks[0].code, "fn main() {}\n");
+        assert_eq!((blocks[0].fence_start, blocks[0].fence_end), (1, Some(3)));
+        assert_eq!(blocks[1].language, None);
+        assert_eq!(blocks[1].code, "```\nplain\n");
+    }
+
+    #[test]
+    fn unclosed_block() {
+        let blocks = parse_code_blocks("```py\nprint(1)\n");
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].fence_end, None);
+        assert!(blocks[0].contains_code_line(1));
+    }
+
+    #[test]
+    fn refence_nested_fences() {
+        let text = "~~~md\nSome code:\n```rust\nfn main() {}\n```\n~~~\n";
+        let block = parse_code_blocks(text).remove(0);
+        let fenced = block.to_fenced();
+        assert!(fenced.starts_with("````md\n"));
+        let reparsed = parse_code_blocks(&fenced);
+        assert_eq!(reparsed.len(), 1);
+        assert_eq!(reparsed[0].language, block.language);
+        assert_eq!(reparsed[0].code, block.code);
+    }
+}
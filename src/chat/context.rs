@@ -0,0 +1,187 @@
+use crate::chat::{Conversation, Message, Role};
+use serde::{Deserialize, Serialize};
+
+/// Which messages of a conversation are sent to the model.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum ContextStrategy {
+    #[default]
+    All,
+    /// Sliding window of the last messages.
+    LastMessages { count: usize },
+    /// Sliding window of the last messages that fit in the token budget.
+    TokenBudget { tokens: usize },
+    /// Keep the last messages and summarize older messages into the system instructions.
+    Summarize { keep: usize },
+}
+
+impl std::fmt::Display for ContextStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::All => write!(f, "all messages"),
+            Self::LastMessages { count } => write!(f, "last {count} messages"),
+            Self::TokenBudget { tokens } => write!(f, "last {tokens} tokens"),
+            Self::Summarize { keep } => write!(f, "summarize all but last {keep} messages"),
+        }
+    }
+}
+
+/// Summary of the messages preceding the context window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub text: String,
+    /// Number of messages from the start of the conversation covered by the summary.
+    pub message_count: usize,
+}
+
+impl Conversation {
+    /// Index of the first message in the context window, earlier messages are only sent if they
+    /// are pinned. Excluded messages are ignored by the context strategy.
+    pub fn context_start(&self, mut count_tokens: impl FnMut(&str) -> usize) -> usize {
+        let included: Vec<(usize, &Message)> = self
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| message.included)
+            .collect();
+        let included_count = included.len();
+        let start = match self.context_strategy {
+            ContextStrategy::All => 0,
+            ContextStrategy::LastMessages { count }
+            | ContextStrategy::Summarize { keep: count } => {
+                included_count.saturating_sub(count.max(1))
+            }
+            ContextStrategy::TokenBudget { tokens } => {
+                let mut used = 0;
+                let mut start = included_count;
+                for (position, (_, message)) in included.iter().enumerate().rev() {
+                    used += count_tokens(&message.content);
+                    // Always include the last message
+                    if used > tokens && start < included_count {
+                        break;
+                    }
+                    start = position;
+                }
+                start
+            }
+        };
+        // The context must start with a user message
+        included
+            .iter()
+            .skip(start)
+            .find(|(_, message)| matches!(message.role, Role::User))
+            .or_else(|| included.get(start))
+            .map_or(self.messages.len(), |(index, _)| *index)
+    }
+
+    /// Check if the message at the given index is sent to the model.
+    #[must_use]
+    pub fn is_in_context(&self, index: usize, context_start: usize) -> bool {
+        self.messages
+            .get(index)
+            .is_some_and(|message| message.included && (message.pinned || index >= context_start))
+    }
+
+    /// Messages sent to the model.
+    pub fn context_messages(&self, count_tokens: impl FnMut(&str) -> usize) -> Vec<&Message> {
+        let context_start = self.context_start(count_tokens);
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.is_in_context(*index, context_start))
+            .map(|(_, message)| message)
+            .collect()
+    }
+
+    /// System instructions sent to the model, including the summary of older messages.
+    #[must_use]
+    pub fn context_system_instructions(&self) -> String {
+        match (&self.context_strategy, &self.summary) {
+            (ContextStrategy::Summarize { .. }, Some(summary)) => format!(
+                "{}\n\nSummary of the earlier conversation:\n{}",
+                self.system_instructions, summary.text
+            ),
+            _ => self.system_instructions.clone(),
+        }
+    }
+
+    /// Check if the messages outside the context should be summarized before sending.
+    #[must_use]
+    pub fn needs_summary(&self, context_start: usize) -> bool {
+        matches!(self.context_strategy, ContextStrategy::Summarize { .. })
+            && context_start
+                > self
+                    .summary
+                    .as_ref()
+                    .map_or(0, |summary| summary.message_count)
+    }
+}
+
+#[cfg(test)]
+mod context_tests {
+    use super::{ContextStrategy, Summary};
+    use crate::chat::{Conversation, Message, Metadata};
+
+    fn conversation(strategy: ContextStrategy) -> Conversation {
+        let mut conversation = Conversation::new("system".to_owned());
+        conversation.context_strategy = strategy;
+        for _ in 0..3 {
+            conversation.add_message(Message::new_user_message("1234".to_owned()));
+            conversation.add_message(Message::new_assistant_message(
+                "model".to_owned(),
+                "12".to_owned(),
+                Metadata::default(),
+            ));
+        }
+        conversation.add_message(Message::new_user_message("1234".to_owned()));
+        conversation
+    }
+
+    #[test]
+    fn context_start() {
+        let count_tokens = |text: &str| text.len();
+        assert_eq!(
+            conversation(ContextStrategy::All).context_start(count_tokens),
+            0
+        );
+        let last_messages = conversation(ContextStrategy::LastMessages { count: 2 });
+        assert_eq!(last_messages.context_start(count_tokens), 6);
+        let last_messages = conversation(ContextStrategy::LastMessages { count: 3 });
+        assert_eq!(last_messages.context_start(count_tokens), 4);
+        let budget = conversation(ContextStrategy::TokenBudget { tokens: 12 });
+        assert_eq!(budget.context_start(count_tokens), 4);
+        let budget = conversation(ContextStrategy::TokenBudget { tokens: 1 });
+        assert_eq!(budget.context_start(count_tokens), 6);
+    }
+
+    #[test]
+    fn included_and_pinned() {
+        let count_tokens = |text: &str| text.len();
+        let mut last_messages = conversation(ContextStrategy::LastMessages { count: 2 });
+        last_messages.messages[5].included = false;
+        last_messages.messages[0].pinned = true;
+        last_messages.messages[1].pinned = true;
+        last_messages.messages[1].included = false;
+        assert_eq!(last_messages.context_start(count_tokens), 4);
+        let context = last_messages.context_messages(count_tokens);
+        assert_eq!(context.len(), 3);
+        assert!(last_messages.is_in_context(0, 4));
+        assert!(!last_messages.is_in_context(1, 4));
+        assert!(!last_messages.is_in_context(5, 4));
+    }
+
+    #[test]
+    fn summary() {
+        let mut summarized = conversation(ContextStrategy::Summarize { keep: 3 });
+        assert!(summarized.needs_summary(4));
+        assert_eq!(summarized.context_system_instructions(), "system");
+        summarized.summary = Some(Summary {
+            text: "earlier".to_owned(),
+            message_count: 4,
+        });
+        assert!(!summarized.needs_summary(4));
+        assert!(summarized
+            .context_system_instructions()
+            .ends_with("earlier"));
+    }
+}
@@ -0,0 +1,302 @@
+use crate::chat::{parse_code_blocks, CodeBlock};
+use anyhow::{Context, Result};
+use std::path::{Component, PathBuf};
+
+const DIFF_LANGUAGES: [&str; 3] = ["diff", "patch", "udiff"];
+const PATH_PREFIXES: [&str; 3] = ["file:", "filename:", "path:"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A unified diff of a single file.
+    Diff(String),
+    /// The full contents of the file.
+    Replace(String),
+    /// A diff deleting the file.
+    Delete,
+}
+
+/// A change to a local file, suggested by a code block in a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilePatch {
+    pub path: PathBuf,
+    pub change: Change,
+}
+
+impl FilePatch {
+    /// Get the patched file contents given the original file contents.
+    pub fn apply(&self, original: &str) -> Result<String> {
+        match &self.change {
+            Change::Replace(contents) => Ok(contents.clone()),
+            Change::Delete => Ok(String::new()),
+            Change::Diff(diff) => {
+                let patch = diffy::Patch::from_str(diff).context("parse diff")?;
+                diffy::apply(original, &patch).context("apply diff")
+            }
+        }
+    }
+
+    /// Resolve the path against the current directory, refusing paths that lead outside of it.
+    pub fn resolve_path(&self) -> Result<PathBuf> {
+        let is_relative = self
+            .path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !is_relative {
+            anyhow::bail!(
+                "refusing path outside the current directory: {}",
+                self.path.display()
+            );
+        }
+        let current_dir = std::env::current_dir()
+            .and_then(|dir| dir.canonicalize())
+            .context("get current directory")?;
+        let path = current_dir.join(&self.path);
+        // Symlinks may still lead outside, so check where the nearest existing ancestor resolves
+        let existing_ancestor = path
+            .ancestors()
+            .find(|ancestor| ancestor.exists())
+            .context("find existing ancestor")?
+            .canonicalize()
+            .context("canonicalize path")?;
+        if !existing_ancestor.starts_with(&current_dir) {
+            anyhow::bail!(
+                "refusing path outside the current directory: {}",
+                self.path.display()
+            );
+        }
+        Ok(path)
+    }
+}
+
+/// Preview of a [`FilePatch`] compared against the local file.
+#[derive(Debug)]
+pub struct PatchPreview {
+    pub patch: FilePatch,
+    pub is_new_file: bool,
+    pub applied: bool,
+    /// The patched file contents and the resulting diff against the local file.
+    pub result: Result<(String, String), String>,
+}
+
+impl PatchPreview {
+    #[must_use]
+    pub fn new(patch: FilePatch, original: Option<&str>) -> Self {
+        let is_new_file = original.is_none();
+        let original = original.unwrap_or_default();
+        let result = patch
+            .apply(original)
+            .map(|patched| {
+                let diff = diffy::create_patch(original, &patched).to_string();
+                (patched, diff)
+            })
+            .map_err(|error| format!("{error:#}"));
+        Self {
+            patch,
+            is_new_file,
+            applied: false,
+            result,
+        }
+    }
+}
+
+/// Find code blocks in the text that are diffs or full files with a known path.
+#[must_use]
+pub fn find_file_patches(text: &str) -> Vec<FilePatch> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut patches = Vec::new();
+    for code_block in parse_code_blocks(text) {
+        if is_diff(&code_block) {
+            patches.extend(split_diff(&code_block.code));
+        } else if let Some(path) = get_code_block_path(&code_block, &lines) {
+            patches.push(FilePatch {
+                path,
+                change: Change::Replace(code_block.code),
+            });
+        }
+    }
+    patches
+}
+
+fn is_diff(code_block: &CodeBlock) -> bool {
+    let language_is_diff = code_block
+        .language
+        .as_deref()
+        .is_some_and(|language| DIFF_LANGUAGES.contains(&language.to_lowercase().as_str()));
+    let first_line = code_block
+        .code
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or_default();
+    language_is_diff || first_line.starts_with("--- ") || first_line.starts_with("diff --git ")
+}
+
+/// Split a (possibly multi-file) unified diff into single-file patches.
+fn split_diff(diff: &str) -> Vec<FilePatch> {
+    let lines: Vec<&str> = diff.lines().collect();
+    let file_starts: Vec<usize> = lines
+        .windows(2)
+        .enumerate()
+        .filter(|(_, pair)| pair[0].starts_with("--- ") && pair[1].starts_with("+++ "))
+        .map(|(index, _)| index)
+        .collect();
+    let mut patches = Vec::new();
+    for (i, start) in file_starts.iter().enumerate() {
+        let end = file_starts.get(i + 1).copied().unwrap_or(lines.len());
+        let Some(path) = get_diff_path(lines[*start], lines[start + 1]) else {
+            continue;
+        };
+        if lines[start + 1]
+            .get(4..)
+            .is_some_and(|path| path.trim() == "/dev/null")
+        {
+            patches.push(FilePatch {
+                path,
+                change: Change::Delete,
+            });
+            continue;
+        }
+        // Drop git extended header lines belonging to the next file
+        let file_lines: Vec<&str> = lines[*start..end]
+            .iter()
+            .copied()
+            .filter(|line| {
+                !(line.starts_with("diff --git ")
+                    || line.starts_with("index ")
+                    || line.starts_with("new file mode ")
+                    || line.starts_with("deleted file mode "))
+            })
+            .collect();
+        let mut file_diff = file_lines.join("\n");
+        file_diff.push('\n');
+        patches.push(FilePatch {
+            path,
+            change: Change::Diff(file_diff),
+        });
+    }
+    patches
+}
+
+fn get_diff_path(original_line: &str, modified_line: &str) -> Option<PathBuf> {
+    let parse_path = |line: &str, git_prefix: &str| {
+        let path = line.get(4..)?.split('\t').next()?.trim();
+        if path.is_empty() || path == "/dev/null" {
+            return None;
+        }
+        Some(PathBuf::from(path.strip_prefix(git_prefix).unwrap_or(path)))
+    };
+    parse_path(modified_line, "b/").or_else(|| parse_path(original_line, "a/"))
+}
+
+/// Get a file path from the code block info string or the line preceding the block.
+fn get_code_block_path(code_block: &CodeBlock, lines: &[&str]) -> Option<PathBuf> {
+    let fence_line = lines.get(code_block.fence_start)?.trim();
+    let info = fence_line.trim_start_matches(['`', '~']);
+    let info_path = info
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .find(|token| is_path_like(token));
+    if let Some(path) = info_path {
+        return Some(PathBuf::from(path));
+    }
+    let preceding_line = lines
+        .get(..code_block.fence_start)?
+        .iter()
+        .rev()
+        .find(|line| !line.trim().is_empty())?;
+    let mut candidate = preceding_line
+        .trim()
+        .trim_matches(['*', '`', '#', ':', ' ']);
+    for prefix in PATH_PREFIXES {
+        if candidate.to_lowercase().starts_with(prefix) {
+            candidate = candidate[prefix.len()..].trim_matches(['*', '`', ' ']);
+        }
+    }
+    is_path_like(candidate).then(|| PathBuf::from(candidate))
+}
+
+fn is_path_like(text: &str) -> bool {
+    !text.is_empty()
+        && (text.contains('.') || text.contains('/'))
+        && !text.contains("://")
+        && !text.ends_with('.')
+        && !text
+            .chars()
+            .any(|c| c.is_whitespace() || "<>|\"*?`,;()[]{}".contains(c))
+}
+
+#[cfg(test)]
+mod patch_tests {
+    use super::{find_file_patches, Change, FilePatch, PatchPreview};
+    use std::path::PathBuf;
+
+    #[test]
+    fn diff_blocks() {
+        let text = "Apply this:\n```diff\ndiff --git a/src/a.rs b/src/a.rs\nindex 1..2\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1 +1 @@\n-old\n+new\n--- /dev/null\n+++ b/new.txt\n@@ -0,0 +1 @@\n+hello\n```\n";
+        let patches = find_file_patches(text);
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].path, PathBuf::from("src/a.rs"));
+        assert_eq!(patches[0].apply("old\n").expect("apply diff"), "new\n");
+        assert_eq!(patches[1].path, PathBuf::from("new.txt"));
+        assert_eq!(patches[1].apply("").expect("apply diff"), "hello\n");
+    }
+
+    #[test]
+    fn deleted_file() {
+        let text = "```diff\n--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-old\n```\n";
+        let patches = find_file_patches(text);
+        assert_eq!(
+            patches,
+            vec![FilePatch {
+                path: PathBuf::from("old.txt"),
+                change: Change::Delete,
+            }]
+        );
+    }
+
+    #[test]
+    fn unsafe_paths() {
+        let resolve = |path: &str| {
+            FilePatch {
+                path: PathBuf::from(path),
+                change: Change::Delete,
+            }
+            .resolve_path()
+        };
+        assert!(resolve("/home/user/.bashrc").is_err());
+        assert!(resolve("../x").is_err());
+        assert!(resolve("src/../../x").is_err());
+        let current_dir = std::env::current_dir().expect("current directory");
+        let resolved = resolve("./src/new_module.rs").expect("resolve path");
+        assert!(resolved.starts_with(current_dir.canonicalize().expect("canonicalize")));
+        assert!(resolved.ends_with("src/new_module.rs"));
+    }
+
+    #[test]
+    fn file_blocks() {
+        let text = "```rust src/main.rs\nfn main() {}\n```\n**`lib.rs`**:\n```rust\n\n```\nRun it:\n```sh\nls\n```\n";
+        let patches = find_file_patches(text);
+        assert_eq!(
+            patches,
+            vec![
+                FilePatch {
+                    path: PathBuf::from("src/main.rs"),
+                    change: Change::Replace("fn main() {}\n".to_owned()),
+                },
+                FilePatch {
+                    path: PathBuf::from("lib.rs"),
+                    change: Change::Replace("\n".to_owned()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_preview() {
+        let patch = FilePatch {
+            path: PathBuf::from("a.txt"),
+            change: Change::Diff("--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-old\n+new\n".into()),
+        };
+        let preview = PatchPreview::new(patch, Some("other\n"));
+        assert!(!preview.is_new_file);
+        assert!(preview.result.is_err());
+    }
+}
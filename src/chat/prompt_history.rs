@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Previously sent prompts, oldest first, stored as JSON lines.
+#[derive(Debug)]
+pub struct PromptHistory {
+    path: PathBuf,
+    max_entries: usize,
+    pub entries: Vec<String>,
+}
+
+impl PromptHistory {
+    /// Load the history, returning the errors of skipped lines.
+    ///
+    /// The file is compacted if it has skipped lines or more than `max_entries` prompts.
+    pub fn from_file(path: &Path, max_entries: usize) -> Result<(Self, Vec<String>)> {
+        let (entries, errors) = crate::read_json_lines(path).context("read prompt history file")?;
+        let mut history = Self {
+            path: path.to_owned(),
+            max_entries,
+            entries,
+        };
+        if history.entries.len() > max_entries || !errors.is_empty() {
+            history.compact()?;
+        }
+        Ok((history, errors))
+    }
+
+    /// Add a prompt and append it to the history file, unless it repeats the last prompt.
+    pub fn record(&mut self, prompt: &str) -> Result<()> {
+        if self.entries.last().is_some_and(|last| last == prompt) {
+            return Ok(());
+        }
+        let mut line = serde_json::to_string(prompt).context("serialize prompt history entry")?;
+        line.push('\n');
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("open prompt history file")?
+            .write_all(line.as_bytes())
+            .context("write prompt history entry")?;
+        self.entries.push(prompt.to_owned());
+        if self.entries.len() > self.max_entries {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    /// Drop the oldest prompts over the limit and rewrite the history file.
+    fn compact(&mut self) -> Result<()> {
+        let excess = self.entries.len().saturating_sub(self.max_entries);
+        self.entries.drain(..excess);
+        let mut data = String::new();
+        for entry in &self.entries {
+            data.push_str(&serde_json::to_string(entry).context("serialize prompt history entry")?);
+            data.push('\n');
+        }
+        crate::write_file(&self.path, &data).context("write prompt history file")
+    }
+
+    /// Index of the newest prompt before the given index that contains the query, ignoring case.
+    #[must_use]
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        let query = query.to_lowercase();
+        self.entries
+            .iter()
+            .take(before)
+            .rposition(|entry| entry.to_lowercase().contains(&query))
+    }
+}
+
+#[cfg(test)]
+mod prompt_history_tests {
+    use super::PromptHistory;
+
+    #[test]
+    fn record_and_search() {
+        let temp_dir = crate::test_dir();
+        let path = temp_dir.path().join("prompt_history.jsonl");
+        let (mut history, _) = PromptHistory::from_file(&path, 10).expect("missing history file");
+        for prompt in ["Review this diff", "multi\nline", "Explain", "Explain"] {
+            history.record(prompt).expect("record prompt");
+        }
+        let (history, errors) = PromptHistory::from_file(&path, 10).expect("load history");
+        assert!(errors.is_empty());
+        assert_eq!(
+            history.entries,
+            ["Review this diff", "multi\nline", "Explain"]
+        );
+        assert_eq!(history.search("", 3), Some(2));
+        assert_eq!(history.search("LINE", 3), Some(1));
+        assert_eq!(history.search("line", 1), None);
+        assert_eq!(history.search("e", 1), Some(0));
+    }
+
+    #[test]
+    fn skip_invalid_lines_and_compact() {
+        let temp_dir = crate::test_dir();
+        let path = temp_dir.path().join("prompt_history.jsonl");
+        std::fs::write(&path, "\"first\"\nnot json\n\"second\"\n\"third\"\n")
+            .expect("write history");
+        let (mut history, errors) = PromptHistory::from_file(&path, 2).expect("load history");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(history.entries, ["second", "third"]);
+        history.record("fourth").expect("record prompt");
+        assert_eq!(history.entries, ["third", "fourth"]);
+        let contents = std::fs::read_to_string(&path).expect("read history");
+        assert_eq!(contents, "\"third\"\n\"fourth\"\n");
+    }
+}
@@ -0,0 +1,140 @@
+use crate::api::TokenUsage;
+use crate::chat::{Conversation, Role};
+use chrono::{Local, NaiveDate};
+use std::collections::{BTreeMap, HashMap};
+
+/// Usage of a single model across conversations.
+#[derive(Debug, Default, Clone)]
+pub struct ModelStats {
+    pub messages: usize,
+    pub usage: TokenUsage,
+    latency_total_ms: u64,
+    latency_count: u64,
+}
+
+impl ModelStats {
+    /// Average response latency in milliseconds, missing if no latency was recorded.
+    #[must_use]
+    pub fn average_latency_ms(&self) -> Option<u64> {
+        self.latency_total_ms.checked_div(self.latency_count)
+    }
+}
+
+/// Statistics aggregated from saved conversations.
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub conversations: usize,
+    pub messages: usize,
+    /// Keyed by provider and model, messages without metadata use the model name.
+    pub models: BTreeMap<(String, String), ModelStats>,
+    /// Assistant responses per local date.
+    pub requests_per_day: BTreeMap<NaiveDate, u64>,
+    /// System instructions and the number of conversations using them, most used first.
+    pub system_instructions: Vec<(String, usize)>,
+}
+
+impl Stats {
+    #[must_use]
+    pub fn from_conversations<'a>(
+        conversations: impl IntoIterator<Item = &'a Conversation>,
+    ) -> Self {
+        let mut stats = Self::default();
+        let mut system_instructions: HashMap<&str, usize> = HashMap::new();
+        for conversation in conversations {
+            if conversation.is_empty() {
+                continue;
+            }
+            stats.conversations += 1;
+            stats.messages += conversation.messages.len();
+            *system_instructions
+                .entry(&conversation.system_instructions)
+                .or_default() += 1;
+            for message in &conversation.messages {
+                let Role::Assistant(model_name) = &message.role else {
+                    continue;
+                };
+                let metadata = &message.metadata;
+                let provider = metadata
+                    .provider
+                    .map_or_else(|| "unknown".to_owned(), |provider| provider.to_string());
+                let model = metadata.model_id.as_ref().unwrap_or(model_name).clone();
+                let model_stats = stats.models.entry((provider, model)).or_default();
+                model_stats.messages += 1;
+                if let Some(usage) = metadata.usage {
+                    model_stats.usage += usage;
+                }
+                if let Some(latency_ms) = metadata.latency_ms {
+                    model_stats.latency_total_ms += latency_ms;
+                    model_stats.latency_count += 1;
+                }
+                if let Some(created_at) = metadata.created_at {
+                    let date = created_at.with_timezone(&Local).date_naive();
+                    *stats.requests_per_day.entry(date).or_default() += 1;
+                }
+            }
+        }
+        let mut system_instructions: Vec<(String, usize)> = system_instructions
+            .into_iter()
+            .map(|(instructions, count)| (instructions.to_owned(), count))
+            .collect();
+        system_instructions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats.system_instructions = system_instructions;
+        stats
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use super::Stats;
+    use crate::api::{Provider, TokenUsage};
+    use crate::chat::{Conversation, Message, Metadata};
+
+    #[test]
+    fn aggregate_conversations() {
+        let response = |latency_ms: Option<u64>| {
+            Message::new_assistant_message(
+                "GPT".to_owned(),
+                String::new(),
+                Metadata {
+                    created_at: Some(chrono::Utc::now()),
+                    provider: Some(Provider::OpenAi),
+                    model_id: Some("gpt".to_owned()),
+                    usage: Some(TokenUsage {
+                        total: 10,
+                        ..Default::default()
+                    }),
+                    latency_ms,
+                    ..Default::default()
+                },
+            )
+        };
+        let mut first = Conversation::new("a".to_owned());
+        first.add_message(Message::new_user_message("hi".to_owned()));
+        first.add_message(response(Some(100)));
+        first.add_message(response(Some(300)));
+        let mut second = Conversation::new("b".to_owned());
+        second.add_message(response(None));
+        let mut third = Conversation::new("b".to_owned());
+        third.add_message(Message::new_assistant_message(
+            "Old".to_owned(),
+            String::new(),
+            Metadata::default(),
+        ));
+        let empty = Conversation::new("c".to_owned());
+
+        let stats = Stats::from_conversations([&first, &second, &third, &empty]);
+        assert_eq!(stats.conversations, 3);
+        assert_eq!(stats.messages, 5);
+        let gpt = &stats.models[&("openai".to_owned(), "gpt".to_owned())];
+        assert_eq!(gpt.messages, 3);
+        assert_eq!(gpt.usage.total, 30);
+        assert_eq!(gpt.average_latency_ms(), Some(200));
+        let old = &stats.models[&("unknown".to_owned(), "Old".to_owned())];
+        assert_eq!(old.average_latency_ms(), None);
+        assert_eq!(stats.requests_per_day.values().sum::<u64>(), 3);
+        assert_eq!(
+            stats.system_instructions,
+            vec![("b".to_owned(), 2), ("a".to_owned(), 1)]
+        );
+    }
+}
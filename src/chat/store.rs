@@ -0,0 +1,453 @@
+use crate::chat::Conversation;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cell::OnceCell;
+use std::path::{Path, PathBuf};
+
+const INDEX_FILE: &str = "index.json";
+const PREVIEW_LENGTH: usize = 100;
+
+/// Summary of a conversation stored in the index, to list conversations without loading them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationEntry {
+    pub id: String,
+    pub title: Option<String>,
+    pub preview: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub message_count: usize,
+    pub last_model: Option<String>,
+}
+
+impl ConversationEntry {
+    fn new(id: String, conversation: &Conversation) -> Self {
+        Self {
+            id,
+            title: conversation.title.clone(),
+            preview: conversation.preview(PREVIEW_LENGTH),
+            created_at: conversation.created_at,
+            updated_at: conversation
+                .messages
+                .last()
+                .and_then(|message| message.metadata.created_at)
+                .or(conversation.created_at),
+            message_count: conversation.messages.len(),
+            last_model: conversation.last_model().map(ToOwned::to_owned),
+        }
+    }
+
+    /// The title, or a preview of the first user message if there is no title.
+    #[must_use]
+    pub fn title_or_preview(&self, length: usize) -> String {
+        self.title
+            .as_deref()
+            .unwrap_or(&self.preview)
+            .chars()
+            .take(length)
+            .collect()
+    }
+}
+
+struct Slot {
+    entry: ConversationEntry,
+    /// Loaded from the conversation file when first accessed.
+    conversation: OnceCell<Conversation>,
+    /// Saved conversations have a file and are listed in the index.
+    saved: bool,
+}
+
+/// Conversations stored as a file each, listed newest first in an index file.
+pub struct ConversationStore {
+    dir: PathBuf,
+    slots: Vec<Slot>,
+}
+
+impl ConversationStore {
+    /// Load the index from the conversations directory, the conversations are loaded on demand.
+    ///
+    /// The index is rebuilt from the conversation files if it is missing or invalid, returning
+    /// warnings about the rebuild and the skipped files.
+    pub fn from_dir(dir: &Path) -> Result<(Self, Vec<String>)> {
+        let mut warnings = Vec::new();
+        let entries: Option<Vec<ConversationEntry>> =
+            match std::fs::read_to_string(dir.join(INDEX_FILE)) {
+                Ok(data) => match serde_json::from_str(&data) {
+                    Ok(entries) => Some(entries),
+                    Err(error) => {
+                        warnings.push(format!("Invalid conversations index: {error}"));
+                        None
+                    }
+                },
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+                Err(error) => return Err(error).context("read conversations index"),
+            };
+        let mut store = Self {
+            dir: dir.to_owned(),
+            slots: Vec::new(),
+        };
+        if let Some(entries) = entries {
+            store.slots = entries
+                .into_iter()
+                .map(|entry| Slot {
+                    entry,
+                    conversation: OnceCell::new(),
+                    saved: true,
+                })
+                .collect();
+        } else {
+            store.rebuild_index(&mut warnings)?;
+            if !store.slots.is_empty() {
+                warnings.push(format!(
+                    "Rebuilt conversations index from {} conversation files",
+                    store.slots.len()
+                ));
+            }
+        }
+        Ok((store, warnings))
+    }
+
+    /// Load the conversation files in the directory newest first and write the index, files
+    /// that fail to load are skipped.
+    fn rebuild_index(&mut self, warnings: &mut Vec<String>) -> Result<()> {
+        for dir_entry in std::fs::read_dir(&self.dir).context("read conversations directory")? {
+            let path = dir_entry
+                .context("read conversations directory entry")?
+                .path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if path.file_name().and_then(|name| name.to_str()) == Some(INDEX_FILE) {
+                continue;
+            }
+            let conversation: Conversation = match std::fs::read_to_string(&path)
+                .context("read file")
+                .and_then(|data| serde_json::from_str(&data).context("deserialize conversation"))
+            {
+                Ok(conversation) => conversation,
+                Err(error) => {
+                    warnings.push(format!("Skipped {}: {error:#}", path.display()));
+                    continue;
+                }
+            };
+            self.slots.push(Slot {
+                entry: ConversationEntry::new(id.to_owned(), &conversation),
+                conversation: OnceCell::from(conversation),
+                saved: true,
+            });
+        }
+        self.slots.sort_by(|a, b| {
+            (b.entry.updated_at, &b.entry.id).cmp(&(a.entry.updated_at, &a.entry.id))
+        });
+        if self.slots.is_empty() {
+            return Ok(());
+        }
+        self.write_index()
+    }
+
+    /// Move the conversations from a single conversations file of older versions into the
+    /// store, the file is renamed with a `.bak` extension afterwards.
+    ///
+    /// Conversations get an id from their position in the file, so conversations that were
+    /// already moved by an interrupted migration are skipped.
+    pub fn migrate_from_file(&mut self, file: &Path) -> Result<usize> {
+        if !file.is_file() {
+            return Ok(0);
+        }
+        let data = std::fs::read_to_string(file).context("read conversations file")?;
+        let conversations: Vec<Conversation> =
+            serde_json::from_str(&data).context("deserialize conversations")?;
+        let mut count = 0;
+        for (position, conversation) in conversations.into_iter().enumerate() {
+            let id = format!("migrated-{position}");
+            if self.position(&id).is_some() {
+                continue;
+            }
+            self.slots.push(Slot {
+                entry: ConversationEntry::new(id, &conversation),
+                conversation: OnceCell::from(conversation),
+                saved: false,
+            });
+            self.save_file(self.slots.len() - 1)?;
+            count += 1;
+        }
+        self.write_index()?;
+        std::fs::rename(file, file.with_extension("json.bak"))
+            .context("rename migrated conversations file")?;
+        Ok(count)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &ConversationEntry> {
+        self.slots.iter().map(|slot| &slot.entry)
+    }
+
+    #[must_use]
+    pub fn position(&self, id: &str) -> Option<usize> {
+        self.slots.iter().position(|slot| slot.entry.id == id)
+    }
+
+    pub fn get(&self, index: usize) -> Result<&Conversation> {
+        let slot = self
+            .slots
+            .get(index)
+            .context("conversation index out of bounds")?;
+        if let Some(conversation) = slot.conversation.get() {
+            return Ok(conversation);
+        }
+        let file = self.get_file(&slot.entry.id);
+        let data = std::fs::read_to_string(&file)
+            .with_context(|| format!("read conversation file {}", file.display()))?;
+        let conversation = serde_json::from_str(&data).context("deserialize conversation file")?;
+        Ok(slot.conversation.get_or_init(|| conversation))
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Result<&mut Conversation> {
+        self.get(index)?;
+        self.slots
+            .get_mut(index)
+            .and_then(|slot| slot.conversation.get_mut())
+            .context("conversation index out of bounds")
+    }
+
+    /// Load all conversations, returning the errors of conversations that failed to load.
+    #[must_use]
+    pub fn load_all(&self) -> (Vec<&Conversation>, Vec<String>) {
+        let mut conversations = Vec::new();
+        let mut errors = Vec::new();
+        for (index, slot) in self.slots.iter().enumerate() {
+            match self.get(index) {
+                Ok(conversation) => conversations.push(conversation),
+                Err(error) => errors.push(format!("{}: {error:#}", slot.entry.id)),
+            }
+        }
+        (conversations, errors)
+    }
+
+    /// Add a conversation, it is written to disk when first saved.
+    pub fn insert(&mut self, index: usize, conversation: Conversation) {
+        let id = self.get_unused_id(&conversation);
+        let slot = Slot {
+            entry: ConversationEntry::new(id, &conversation),
+            conversation: OnceCell::from(conversation),
+            saved: false,
+        };
+        self.slots.insert(index.min(self.slots.len()), slot);
+    }
+
+    /// Remove a conversation, deleting its file.
+    pub fn remove(&mut self, index: usize) -> Result<Conversation> {
+        self.get(index)?;
+        let mut slot = self.slots.remove(index);
+        if slot.saved {
+            std::fs::remove_file(self.get_file(&slot.entry.id))
+                .context("delete conversation file")?;
+            self.write_index()?;
+        }
+        slot.conversation
+            .take()
+            .context("removed conversation not loaded")
+    }
+
+    /// Write the conversation to its file and update the index.
+    ///
+    /// Conversations without messages or a draft are not kept on disk, their file is deleted if
+    /// they were saved before.
+    pub fn save(&mut self, index: usize) -> Result<()> {
+        let conversation = self.get(index)?;
+        if conversation.is_empty() && conversation.draft.trim().is_empty() {
+            return self.delete_file(index);
+        }
+        self.save_file(index)?;
+        self.write_index()
+    }
+
+    fn delete_file(&mut self, index: usize) -> Result<()> {
+        let slot = self
+            .slots
+            .get_mut(index)
+            .context("conversation index out of bounds")?;
+        if !slot.saved {
+            return Ok(());
+        }
+        slot.saved = false;
+        let id = slot.entry.id.clone();
+        std::fs::remove_file(self.get_file(&id)).context("delete conversation file")?;
+        self.write_index()
+    }
+
+    fn save_file(&mut self, index: usize) -> Result<()> {
+        let conversation = self.get(index)?;
+        let data = serde_json::to_string(conversation).context("serialize conversation")?;
+        let slot = self
+            .slots
+            .get(index)
+            .context("conversation index out of bounds")?;
+        let entry = ConversationEntry::new(slot.entry.id.clone(), conversation);
+        crate::write_file(&self.get_file(&entry.id), &data).context("write conversation file")?;
+        if let Some(slot) = self.slots.get_mut(index) {
+            slot.entry = entry;
+            slot.saved = true;
+        }
+        Ok(())
+    }
+
+    fn write_index(&self) -> Result<()> {
+        let entries: Vec<&ConversationEntry> = self
+            .slots
+            .iter()
+            .filter(|slot| slot.saved)
+            .map(|slot| &slot.entry)
+            .collect();
+        let data = serde_json::to_string(&entries).context("serialize conversations index")?;
+        crate::write_file(&self.dir.join(INDEX_FILE), &data).context("write conversations index")
+    }
+
+    fn get_file(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    fn get_unused_id(&self, conversation: &Conversation) -> String {
+        let id = conversation
+            .created_at
+            .unwrap_or_else(Utc::now)
+            .format("%Y%m%d-%H%M%S-%9f")
+            .to_string();
+        let is_used = |candidate: &str| {
+            self.position(candidate).is_some() || self.get_file(candidate).exists()
+        };
+        let mut candidate = id.clone();
+        let mut number = 1;
+        while is_used(&candidate) {
+            number += 1;
+            candidate = format!("{id}-{number}");
+        }
+        candidate
+    }
+}
+
+#[cfg(test)]
+mod store_tests {
+    use super::ConversationStore;
+    use crate::chat::{Conversation, Message};
+
+    #[test]
+    fn save_and_load() {
+        let temp_dir = crate::test_dir();
+        let legacy_file = temp_dir.path().join("conversations.json");
+        let dir = &temp_dir.path().join("conversations");
+        std::fs::create_dir(dir).expect("create conversations directory");
+        let mut legacy = Conversation::new("legacy".to_owned());
+        legacy.title = Some("Migrated".to_owned());
+        let data = serde_json::to_string(&[legacy]).expect("serialize legacy conversations");
+        std::fs::write(&legacy_file, data).expect("write legacy conversations");
+
+        let (mut store, warnings) = ConversationStore::from_dir(dir).expect("load empty store");
+        assert!(warnings.is_empty());
+        assert_eq!(store.migrate_from_file(&legacy_file).expect("migrate"), 1);
+        let mut first = Conversation::new("first".to_owned());
+        first.add_message(Message::new_user_message("hello there".to_owned()));
+        store.insert(0, first);
+        store.insert(0, Conversation::new("unsaved".to_owned()));
+        store.save(1).expect("save conversation");
+
+        let (store, _) = ConversationStore::from_dir(dir).expect("load store");
+        let saved_files = std::fs::read_dir(dir).expect("read dir").count();
+        // Two conversations and the index
+        assert_eq!(saved_files, 3);
+        assert_eq!(store.len(), 2);
+        let titles: Vec<String> = store
+            .entries()
+            .map(|entry| entry.title_or_preview(20))
+            .collect();
+        assert_eq!(titles, ["hello there", "Migrated"]);
+        let migrated = store.get(1).expect("load conversation");
+        assert_eq!(migrated.system_instructions, "legacy");
+    }
+
+    #[test]
+    fn skip_empty_conversations() {
+        let temp_dir = crate::test_dir();
+        let dir = temp_dir.path();
+        let (mut store, _) = ConversationStore::from_dir(dir).expect("load empty store");
+        store.insert(0, Conversation::new("empty".to_owned()));
+        store.get_mut(0).expect("get conversation").draft = "draft".to_owned();
+        store.get_mut(0).expect("get conversation").draft.clear();
+        store.save(0).expect("save conversation");
+        assert_eq!(std::fs::read_dir(dir).expect("read dir").count(), 0);
+
+        store.get_mut(0).expect("get conversation").draft = "draft".to_owned();
+        store.save(0).expect("save conversation");
+        assert_eq!(
+            ConversationStore::from_dir(dir)
+                .expect("load store")
+                .0
+                .len(),
+            1
+        );
+        store.get_mut(0).expect("get conversation").draft.clear();
+        store.save(0).expect("save conversation");
+        assert_eq!(
+            ConversationStore::from_dir(dir)
+                .expect("load store")
+                .0
+                .len(),
+            0
+        );
+        // Only the index is left
+        assert_eq!(std::fs::read_dir(dir).expect("read dir").count(), 1);
+    }
+
+    #[test]
+    fn rebuild_index_and_resume_migration() {
+        let temp_dir = crate::test_dir();
+        let legacy_file = temp_dir.path().join("conversations.json");
+        let dir = &temp_dir.path().join("conversations");
+        std::fs::create_dir(dir).expect("create conversations directory");
+        let legacy = ["first", "second"].map(|instructions| {
+            let mut conversation = Conversation::new(instructions.to_owned());
+            conversation.add_message(Message::new_user_message(instructions.to_owned()));
+            conversation
+        });
+        let data = serde_json::to_string(&legacy).expect("serialize legacy conversations");
+        std::fs::write(&legacy_file, data).expect("write legacy conversations");
+        let (mut store, _) = ConversationStore::from_dir(dir).expect("load empty store");
+        assert_eq!(store.migrate_from_file(&legacy_file).expect("migrate"), 2);
+        // Interrupted before the legacy file was renamed, with a lost index
+        std::fs::rename(legacy_file.with_extension("json.bak"), &legacy_file)
+            .expect("restore legacy file");
+        std::fs::remove_file(dir.join("index.json")).expect("delete index");
+        std::fs::write(dir.join("broken.json"), "{").expect("write broken conversation");
+
+        let (mut store, warnings) = ConversationStore::from_dir(dir).expect("rebuild index");
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.migrate_from_file(&legacy_file).expect("migrate"), 0);
+        assert_eq!(store.len(), 2);
+        let (conversations, errors) = store.load_all();
+        assert!(errors.is_empty());
+        let mut instructions: Vec<&str> = conversations
+            .iter()
+            .map(|conversation| conversation.system_instructions.as_str())
+            .collect();
+        instructions.sort_unstable();
+        assert_eq!(instructions, ["first", "second"]);
+
+        std::fs::write(dir.join("index.json"), "not json").expect("corrupt index");
+        let (store, warnings) = ConversationStore::from_dir(dir).expect("rebuild index");
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(store.len(), 2);
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+const ESCAPE: char = '\\';
+
+/// Replace `{{variable}}` placeholders in the text with their resolved values.
+///
+/// Each variable is resolved once, placeholders of unknown variables (resolved to `None`) are
+/// left as they are. A placeholder is escaped with a backslash (`\{{variable}}`) to keep it as
+/// literal text.
+pub fn expand_template(text: &str, mut resolve: impl FnMut(&str) -> Option<String>) -> String {
+    let mut resolved: HashMap<&str, Option<String>> = HashMap::new();
+    let mut expanded = String::with_capacity(text.len());
+    let mut remaining = text;
+    while let Some(start) = remaining.find(OPEN) {
+        if remaining[..start].ends_with(ESCAPE) {
+            expanded.push_str(&remaining[..start - ESCAPE.len_utf8()]);
+            expanded.push_str(OPEN);
+            remaining = &remaining[start + OPEN.len()..];
+            continue;
+        }
+        let after_open = &remaining[start + OPEN.len()..];
+        let Some(end) = after_open.find(CLOSE) else {
+            break;
+        };
+        let name = after_open[..end].trim();
+        let placeholder_end = start + OPEN.len() + end + CLOSE.len();
+        expanded.push_str(&remaining[..start]);
+        let value = if is_valid_variable_name(name) {
+            resolved
+                .entry(name)
+                .or_insert_with(|| resolve(name))
+                .as_deref()
+        } else {
+            None
+        };
+        expanded.push_str(value.unwrap_or(&remaining[start..placeholder_end]));
+        remaining = &remaining[placeholder_end..];
+    }
+    expanded.push_str(remaining);
+    expanded
+}
+
+fn is_valid_variable_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_' || char == '-')
+}
+
+#[cfg(test)]
+mod template_tests {
+    use super::expand_template;
+
+    #[test]
+    fn placeholders() {
+        let mut calls = 0;
+        let expanded = expand_template(
+            "{{name}} in {{ dir }}, {{name}}! {{unknown}} {{not valid}} {{open",
+            |name| {
+                calls += 1;
+                match name {
+                    "name" => Some("Parrot".to_owned()),
+                    "dir" => Some("/tmp".to_owned()),
+                    _ => None,
+                }
+            },
+        );
+        assert_eq!(
+            expanded,
+            "Parrot in /tmp, Parrot! {{unknown}} {{not valid}} {{open"
+        );
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn escaped_placeholders() {
+        let expanded = expand_template(r"\{{name}} is {{name}}, \{{ name }}", |name| {
+            (name == "name").then(|| "Parrot".to_owned())
+        });
+        assert_eq!(expanded, "{{name}} is Parrot, {{ name }}");
+    }
+}
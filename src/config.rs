@@ -4,11 +4,23 @@ use anyhow::{Context, Result};
 use serde::Deserialize;
 use std::path::Path;
 
+mod limits;
 mod models;
+mod named_files;
+mod snippets;
 mod system;
 mod ui;
+mod variables;
 
-pub use models::Models;
+pub use limits::{Limits, ProviderLimits};
+pub use models::{ModelEntry, ModelField, Models};
+pub use named_files::{get_named_file, is_valid_name};
+pub use snippets::{filter_snippets, load_snippets, Snippet};
+pub use system::{
+    get_preset_differences, get_unused_preset_name, load_presets, SystemInstructions,
+};
+pub use ui::Layout;
+pub use variables::{Variable, Variables};
 
 const CONFIG_TEMPLATE: &str = include_str!("../config.template.toml");
 
@@ -19,6 +31,20 @@ pub struct Config {
     pub ui: ui::Ui,
     pub commands: Commands,
     pub system: system::System,
+    // Sections added after the first release default to the template values, so existing
+    // config files keep working
+    #[serde(default)]
+    pub titles: Titles,
+    #[serde(default)]
+    pub context: ContextStrategies,
+    #[serde(default)]
+    pub limits: Limits,
+    #[serde(default)]
+    pub variables: Variables,
+    #[serde(default)]
+    pub session: SessionConfig,
+    #[serde(default)]
+    pub history: History,
     pub hotkeys: HotkeyConfig,
 }
 
@@ -32,7 +58,25 @@ impl Config {
         // Read and parse file
         let config_file_contents =
             std::fs::read_to_string(config_file).context("read config file")?;
-        toml::from_str(&config_file_contents).context("parse config file toml")
+        let mut config: Self =
+            toml::from_str(&config_file_contents).context("parse config file toml")?;
+        config.add_default_hotkeys()?;
+        Ok(config)
+    }
+
+    /// Bind actions missing from the config to the template hotkeys, so actions added after the
+    /// config file was created can be used. An action can be unbound with an empty list.
+    fn add_default_hotkeys(&mut self) -> Result<()> {
+        #[derive(Deserialize)]
+        struct TemplateHotkeys {
+            hotkeys: HotkeyConfig,
+        }
+        let template: TemplateHotkeys =
+            toml::from_str(CONFIG_TEMPLATE).context("parse template hotkeys")?;
+        for (action, events) in template.hotkeys {
+            self.hotkeys.entry(action).or_insert(events);
+        }
+        Ok(())
     }
 }
 
@@ -42,6 +86,70 @@ pub struct ApiKeys {
     pub anthropic: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Titles {
+    pub generate: bool,
+    pub openai_model: String,
+    pub anthropic_model: String,
+}
+
+impl Default for Titles {
+    fn default() -> Self {
+        Self {
+            generate: true,
+            openai_model: "gpt-4o-mini".to_owned(),
+            anthropic_model: "claude-3-5-haiku-latest".to_owned(),
+        }
+    }
+}
+
+/// Values used when cycling the context strategy of a conversation.
+#[derive(Debug, Deserialize)]
+pub struct ContextStrategies {
+    pub last_messages: usize,
+    pub token_budget: usize,
+    pub summarize_keep: usize,
+    pub openai_summary_model: String,
+    pub anthropic_summary_model: String,
+}
+
+impl Default for ContextStrategies {
+    fn default() -> Self {
+        Self {
+            last_messages: 20,
+            token_budget: 16000,
+            summarize_keep: 10,
+            openai_summary_model: "gpt-4o-mini".to_owned(),
+            anthropic_summary_model: "claude-3-5-haiku-latest".to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Reopen the last active conversation, tab, focus and scroll position on launch.
+    pub restore: bool,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self { restore: true }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct History {
+    /// Number of sent prompts kept in the prompt history, older prompts are dropped.
+    pub max_entries: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self { max_entries: 1000 }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Commands {
     pub editor: Vec<String>,
@@ -50,12 +158,77 @@ pub struct Commands {
 
 #[cfg(test)]
 mod config_tests {
-    use super::Config;
+    use super::{Config, SessionConfig, CONFIG_TEMPLATE};
+    use crate::app::hotkeys::HotkeyAction;
     use std::path::Path;
 
     #[test]
     fn config_template() {
         let template_file = Path::new("config.template.toml");
-        Config::from_file(template_file, false).expect("load config from template");
+        let config = Config::from_file(template_file, false).expect("load config from template");
+        assert!(config.limits.openai.monthly_cost.is_none());
+        assert!(config.limits.anthropic.monthly_cost.is_none());
+    }
+
+    #[test]
+    fn config_without_added_sections() {
+        let mut table: toml::Table = toml::from_str(CONFIG_TEMPLATE).expect("parse template");
+        for section in [
+            "titles",
+            "context",
+            "limits",
+            "variables",
+            "session",
+            "history",
+        ] {
+            table.remove(section);
+        }
+        let ui = table
+            .get_mut("ui")
+            .and_then(toml::Value::as_table_mut)
+            .expect("ui section");
+        ui.remove("highlight");
+        ui.get_mut("layout")
+            .and_then(toml::Value::as_table_mut)
+            .expect("layout section")
+            .retain(|key, _| key == "prompt_size");
+        let config: Config = table
+            .try_into()
+            .expect("load config without added sections");
+        assert_eq!(config.ui.highlight.theme, "base16-eighties.dark");
+        assert!(!config.ui.layout.sidebar);
+        assert!(config.ui.layout.message_metadata);
+        assert!(config.titles.generate);
+        assert!(config.session.restore);
+        let session: SessionConfig = toml::from_str("").expect("load empty session section");
+        assert!(session.restore);
+        assert_eq!(config.history.max_entries, 1000);
+        assert!(config
+            .limits
+            .get(crate::api::Provider::OpenAi)
+            .monthly_cost
+            .is_none());
+    }
+
+    #[test]
+    fn default_hotkeys() {
+        let mut table: toml::Table = toml::from_str(CONFIG_TEMPLATE).expect("parse template");
+        let hotkeys = table
+            .get_mut("hotkeys")
+            .and_then(toml::Value::as_table_mut)
+            .expect("hotkeys section");
+        hotkeys.remove("rename");
+        hotkeys.insert("snippets".to_owned(), toml::Value::Array(Vec::new()));
+        let mut config: Config = table.try_into().expect("load config");
+        config.add_default_hotkeys().expect("add default hotkeys");
+        let is_bound = |action| {
+            config
+                .hotkeys
+                .get(&action)
+                .and_then(Option::as_ref)
+                .is_some_and(|events| !events.is_empty())
+        };
+        assert!(is_bound(HotkeyAction::Rename));
+        assert!(!is_bound(HotkeyAction::Snippets));
     }
 }
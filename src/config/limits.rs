@@ -0,0 +1,48 @@
+use crate::api::Provider;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Limits {
+    /// Fraction of a limit at which to start warning.
+    #[serde(default = "default_warn_ratio")]
+    pub warn_ratio: f64,
+    #[serde(default)]
+    pub openai: ProviderLimits,
+    #[serde(default)]
+    pub anthropic: ProviderLimits,
+}
+
+/// No limits are enforced when the section is missing.
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            warn_ratio: default_warn_ratio(),
+            openai: ProviderLimits::default(),
+            anthropic: ProviderLimits::default(),
+        }
+    }
+}
+
+fn default_warn_ratio() -> f64 {
+    0.8
+}
+
+impl Limits {
+    #[must_use]
+    pub fn get(&self, provider: Provider) -> &ProviderLimits {
+        match provider {
+            Provider::OpenAi => &self.openai,
+            Provider::Anthropic => &self.anthropic,
+        }
+    }
+}
+
+/// Hard limits on usage of a provider, costs are in USD.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ProviderLimits {
+    pub daily_cost: Option<f64>,
+    pub monthly_cost: Option<f64>,
+    pub daily_tokens: Option<u32>,
+    pub monthly_tokens: Option<u32>,
+}
@@ -1,10 +1,13 @@
-use crate::api::Provider;
+use crate::api::{Capabilities, Capability, Parameter, Parameters, Pricing, Provider, RemoteModel};
 use anyhow::{Context, Result};
-use serde::Deserialize;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use strum::IntoEnumIterator;
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Table, Value};
 
 const OPENAI_MODELS_TEMPLATE: &str = include_str!("../../models/openai.toml");
 const ANTHROPIC_MODELS_TEMPLATE: &str = include_str!("../../models/anthropic.toml");
+const CONTEXT_WINDOW_STEP: u32 = 8192;
 
 #[derive(Debug)]
 pub struct Models {
@@ -20,18 +23,443 @@ impl Models {
         let anthropic = get_anthropic_models_from_file(&anthropic_models_file, generate_missing)?;
         Ok(Self { openai, anthropic })
     }
+
+    /// Remote models of the provider that are not in its models file.
+    #[must_use]
+    pub fn get_unconfigured<'a>(
+        &self,
+        provider: Provider,
+        remote_models: &'a [RemoteModel],
+    ) -> Vec<&'a RemoteModel> {
+        let configured: Vec<&str> = match provider {
+            Provider::OpenAi => self.openai.iter().map(ModelEntry::id).collect(),
+            Provider::Anthropic => self.anthropic.iter().map(ModelEntry::id).collect(),
+        };
+        remote_models
+            .iter()
+            .filter(|remote_model| !configured.contains(&remote_model.id.as_str()))
+            .collect()
+    }
+
+    /// Add a remote model to the models of the provider, using the entry of the template with the
+    /// same id if there is one, since the APIs do not list context windows or prices.
+    ///
+    /// Returns whether the pricing of the added model is known.
+    pub fn add_remote(&mut self, provider: Provider, remote_model: &RemoteModel) -> bool {
+        match provider {
+            Provider::OpenAi => {
+                let template = toml::from_str::<OpenAi>(OPENAI_MODELS_TEMPLATE)
+                    .map(|template| template.models)
+                    .unwrap_or_default();
+                add_remote_model(&mut self.openai, &template, remote_model)
+            }
+            Provider::Anthropic => {
+                let template = toml::from_str::<Anthropic>(ANTHROPIC_MODELS_TEMPLATE)
+                    .map(|template| template.models)
+                    .unwrap_or_default();
+                add_remote_model(&mut self.anthropic, &template, remote_model)
+            }
+        }
+    }
+
+    /// Number of models configured for the provider.
+    #[must_use]
+    pub fn count(&self, provider: Provider) -> usize {
+        match provider {
+            Provider::OpenAi => self.openai.len(),
+            Provider::Anthropic => self.anthropic.len(),
+        }
+    }
+
+    /// Write the models of the provider back to its models file, changing only the edited values
+    /// so that comments and formatting of the file or the template are kept.
+    pub fn to_disk(&self, models_dir: &Path, provider: Provider) -> Result<PathBuf> {
+        let models_file = models_dir.join(format!("{provider}.toml"));
+        let models_toml = match provider {
+            Provider::OpenAi => toml::to_string_pretty(&OpenAi {
+                models: self.openai.clone(),
+            }),
+            Provider::Anthropic => toml::to_string_pretty(&Anthropic {
+                models: self.anthropic.clone(),
+            }),
+        }
+        .context("serialize models toml")?;
+        let existing = std::fs::read_to_string(&models_file).unwrap_or_else(|_| {
+            match provider {
+                Provider::OpenAi => OPENAI_MODELS_TEMPLATE,
+                Provider::Anthropic => ANTHROPIC_MODELS_TEMPLATE,
+            }
+            .to_owned()
+        });
+        let contents = patch_models_toml(&existing, &models_toml).context("patch models toml")?;
+        crate::write_file(&models_file, &contents).context("write models file")?;
+        Ok(models_file)
+    }
+}
+
+/// Replace the models of the existing toml with the updated models, matched by id.
+///
+/// Unchanged values keep their formatting and comments, and comments before the first model (the
+/// header of the file) stay at the top.
+fn patch_models_toml(existing: &str, updated: &str) -> Result<String> {
+    let mut document: DocumentMut = existing.parse().context("parse existing models toml")?;
+    let updated: DocumentMut = updated.parse().context("parse updated models toml")?;
+    let existing_models = document
+        .get("models")
+        .and_then(Item::as_array_of_tables)
+        .cloned()
+        .unwrap_or_default();
+    let header = existing_models
+        .get(0)
+        .map(|table| table.decor().prefix().cloned());
+    let mut models = ArrayOfTables::new();
+    for updated_table in updated
+        .get("models")
+        .and_then(Item::as_array_of_tables)
+        .into_iter()
+        .flatten()
+    {
+        let id = updated_table.get("id").and_then(Item::as_str);
+        let mut table = existing_models
+            .iter()
+            .find(|table| id.is_some() && table.get("id").and_then(Item::as_str) == id)
+            .cloned()
+            .unwrap_or_else(|| {
+                let mut table = Table::new();
+                table.decor_mut().set_prefix("\n");
+                table
+            });
+        table.retain(|key, _| updated_table.contains_key(key));
+        for (key, updated_item) in updated_table {
+            let Some(mut value) = to_value(updated_item) else {
+                continue;
+            };
+            match table.get_mut(key) {
+                Some(Item::Value(existing_value)) if same_value(existing_value, &value) => (),
+                Some(Item::Value(existing_value)) => {
+                    *value.decor_mut() = existing_value.decor().clone();
+                    *existing_value = value;
+                }
+                _ => {
+                    table.insert(key, Item::Value(value));
+                }
+            }
+        }
+        models.push(table);
+    }
+    // The header comments are parsed as the prefix of the first model
+    if let Some(header) = header {
+        for (index, table) in models.iter_mut().enumerate() {
+            let prefix = if index == 0 {
+                header.clone().unwrap_or_default()
+            } else if table.decor().prefix() == header.as_ref() {
+                "\n".into()
+            } else {
+                continue;
+            };
+            table.decor_mut().set_prefix(prefix);
+        }
+    }
+    document.insert("models", Item::ArrayOfTables(models));
+    Ok(document.to_string())
+}
+
+/// Models are written with inline tables, as in the templates.
+fn to_value(item: &Item) -> Option<Value> {
+    match item {
+        Item::Value(value) => Some(value.clone()),
+        Item::Table(table) => {
+            let mut inline_table = table.clone().into_inline_table();
+            inline_table.fmt();
+            Some(Value::InlineTable(inline_table))
+        }
+        Item::None | Item::ArrayOfTables(_) => None,
+    }
+}
+
+/// Compare the values regardless of formatting.
+fn same_value(a: &Value, b: &Value) -> bool {
+    let parse = |value: &Value| format!("value = {value}").parse::<toml::Table>().ok();
+    parse(a).is_some_and(|a| Some(a) == parse(b))
 }
 
-#[derive(Deserialize)]
+fn add_remote_model<M: ModelEntry>(
+    models: &mut Vec<M>,
+    template: &[M],
+    remote_model: &RemoteModel,
+) -> bool {
+    let model = template
+        .iter()
+        .find(|model| model.id() == remote_model.id)
+        .cloned()
+        .unwrap_or_else(|| M::from_remote(remote_model));
+    let has_pricing = model.get_pricing().is_some();
+    models.push(model);
+    has_pricing
+}
+
+#[derive(Serialize, Deserialize)]
 struct OpenAi {
     pub models: Vec<crate::api::openai::Model>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 struct Anthropic {
     pub models: Vec<crate::api::anthropic::Model>,
 }
 
+/// A field of a model entry shown in the model editor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelField {
+    Id,
+    Name,
+    Parameter(Parameter),
+    ContextWindow,
+    Pricing,
+    Capability(Capability),
+}
+
+impl ModelField {
+    /// Fields of the models of the provider, in display order.
+    #[must_use]
+    pub fn all(provider: Provider) -> Vec<Self> {
+        [Self::Id, Self::Name]
+            .into_iter()
+            .chain(
+                Parameter::iter()
+                    .filter(|parameter| parameter.is_supported(provider))
+                    .map(Self::Parameter),
+            )
+            .chain([Self::ContextWindow, Self::Pricing])
+            .chain(Capability::iter().map(Self::Capability))
+            .collect()
+    }
+
+    /// Check if the field can be changed with increment and decrement.
+    #[must_use]
+    pub fn is_adjustable(self) -> bool {
+        matches!(
+            self,
+            Self::Parameter(_) | Self::ContextWindow | Self::Capability(_)
+        )
+    }
+
+    #[must_use]
+    pub fn is_text(self) -> bool {
+        matches!(self, Self::Id | Self::Name)
+    }
+}
+
+impl std::fmt::Display for ModelField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Id => write!(f, "id"),
+            Self::Name => write!(f, "name"),
+            Self::Parameter(parameter) => write!(f, "{parameter}"),
+            Self::ContextWindow => write!(f, "context_window"),
+            Self::Pricing => write!(f, "pricing"),
+            Self::Capability(capability) => write!(f, "capabilities.{capability}"),
+        }
+    }
+}
+
+/// A model entry of a models file that can be edited in the config tab.
+pub trait ModelEntry: Clone + Default {
+    const PROVIDER: Provider;
+
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
+    fn text_field_mut(&mut self, field: ModelField) -> Option<&mut String>;
+    fn context_window(&self) -> Option<u32>;
+    fn context_window_mut(&mut self) -> &mut Option<u32>;
+    fn get_parameters(&self) -> Parameters;
+    fn set_parameters(&mut self, parameters: &Parameters);
+    fn get_pricing(&self) -> Option<Pricing>;
+    fn capabilities_mut(&mut self) -> &mut Capabilities;
+    fn get_capabilities(&self) -> Capabilities;
+
+    /// New entry for a remote model with default parameters.
+    #[must_use]
+    fn from_remote(remote_model: &RemoteModel) -> Self {
+        let mut model = Self::default();
+        model.set_text_field(ModelField::Id, remote_model.id.clone());
+        model.set_text_field(ModelField::Name, remote_model.name.clone());
+        model
+    }
+
+    /// Display value of the field, missing if not set.
+    fn get_field(&self, field: ModelField) -> Option<String> {
+        match field {
+            ModelField::Id => Some(self.id().to_owned()),
+            ModelField::Name => Some(self.name().to_owned()),
+            ModelField::Parameter(parameter) => self.get_parameters().get_display(parameter),
+            ModelField::ContextWindow => self.context_window().map(|tokens| tokens.to_string()),
+            ModelField::Pricing => self.get_pricing().map(|pricing| {
+                let cached_input = pricing
+                    .cached_input
+                    .map(|price| format!(" · cached input ${price}"))
+                    .unwrap_or_default();
+                format!(
+                    "input ${}{cached_input} · output ${} per million tokens",
+                    pricing.input, pricing.output
+                )
+            }),
+            ModelField::Capability(capability) => Some(
+                if self.get_capabilities().get(capability) {
+                    "yes"
+                } else {
+                    "no"
+                }
+                .to_owned(),
+            ),
+        }
+    }
+
+    /// Increase or decrease a numeric field or toggle a capability, other fields are left
+    /// unchanged.
+    fn step_field(&mut self, field: ModelField, increase: bool) {
+        match field {
+            ModelField::Parameter(parameter) => {
+                let defaults = self.get_parameters();
+                let mut parameters = defaults.clone();
+                parameters.step(parameter, Self::PROVIDER, &defaults, increase);
+                self.set_parameters(&parameters);
+            }
+            ModelField::ContextWindow => {
+                let context_window = self.context_window_mut();
+                let tokens = context_window.unwrap_or_default();
+                let tokens = if increase {
+                    tokens.saturating_add(CONTEXT_WINDOW_STEP)
+                } else {
+                    tokens.saturating_sub(CONTEXT_WINDOW_STEP)
+                };
+                *context_window = Some(tokens).filter(|tokens| *tokens > 0);
+            }
+            ModelField::Capability(capability) => self.capabilities_mut().toggle(capability),
+            ModelField::Id | ModelField::Name | ModelField::Pricing => (),
+        }
+    }
+
+    /// Set a text field, other fields are left unchanged.
+    fn set_text_field(&mut self, field: ModelField, value: String) {
+        if let Some(text) = self.text_field_mut(field) {
+            *text = value;
+        }
+    }
+}
+
+impl ModelEntry for crate::api::openai::Model {
+    const PROVIDER: Provider = Provider::OpenAi;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn text_field_mut(&mut self, field: ModelField) -> Option<&mut String> {
+        match field {
+            ModelField::Id => Some(&mut self.id),
+            ModelField::Name => Some(&mut self.name),
+            _ => None,
+        }
+    }
+
+    fn context_window(&self) -> Option<u32> {
+        self.context_window
+    }
+
+    fn context_window_mut(&mut self) -> &mut Option<u32> {
+        &mut self.context_window
+    }
+
+    fn get_parameters(&self) -> Parameters {
+        self.parameters()
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        self.set_parameters(parameters);
+    }
+
+    fn get_pricing(&self) -> Option<Pricing> {
+        self.pricing
+    }
+
+    fn capabilities_mut(&mut self) -> &mut Capabilities {
+        &mut self.capabilities
+    }
+
+    fn get_capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    fn from_remote(remote_model: &RemoteModel) -> Self {
+        let is_reasoning = crate::api::openai::is_reasoning_model_id(&remote_model.id);
+        // Reasoning models take instructions with the developer role and reject sampling
+        let capabilities = Capabilities {
+            developer_role: is_reasoning,
+            temperature: !is_reasoning,
+            ..Capabilities::default()
+        };
+        Self {
+            id: remote_model.id.clone(),
+            name: remote_model.name.clone(),
+            capabilities,
+            ..Self::default()
+        }
+    }
+}
+
+impl ModelEntry for crate::api::anthropic::Model {
+    const PROVIDER: Provider = Provider::Anthropic;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn text_field_mut(&mut self, field: ModelField) -> Option<&mut String> {
+        match field {
+            ModelField::Id => Some(&mut self.id),
+            ModelField::Name => Some(&mut self.name),
+            _ => None,
+        }
+    }
+
+    fn context_window(&self) -> Option<u32> {
+        self.context_window
+    }
+
+    fn context_window_mut(&mut self) -> &mut Option<u32> {
+        &mut self.context_window
+    }
+
+    fn get_parameters(&self) -> Parameters {
+        self.parameters()
+    }
+
+    fn set_parameters(&mut self, parameters: &Parameters) {
+        self.set_parameters(parameters);
+    }
+
+    fn get_pricing(&self) -> Option<Pricing> {
+        self.pricing
+    }
+
+    fn capabilities_mut(&mut self) -> &mut Capabilities {
+        &mut self.capabilities
+    }
+
+    fn get_capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+}
+
 fn get_openai_models_from_file(
     config_file: &Path,
     generate_missing: bool,
@@ -43,11 +471,46 @@ fn get_openai_models_from_file(
     }
     let models_file_contents =
         std::fs::read_to_string(config_file).context("read openai models file")?;
-    Ok(toml::from_str::<OpenAi>(&models_file_contents)
+    let mut models_toml =
+        toml::from_str::<toml::Table>(&models_file_contents).context("parse openai models toml")?;
+    migrate_model_classes(&mut models_toml).context("migrate openai model classes")?;
+    Ok(toml::Value::Table(models_toml)
+        .try_into::<OpenAi>()
         .context("parse openai models toml")?
         .models)
 }
 
+/// Replace the model `class` of older versions with the equivalent capabilities.
+fn migrate_model_classes(models_toml: &mut toml::Table) -> Result<()> {
+    let Some(models) = models_toml
+        .get_mut("models")
+        .and_then(toml::Value::as_array_mut)
+    else {
+        return Ok(());
+    };
+    for model in models.iter_mut().filter_map(toml::Value::as_table_mut) {
+        let Some(class) = model.remove("class") else {
+            continue;
+        };
+        match class.as_str() {
+            Some("classic") => (),
+            // Reasoning models took instructions as a user message and rejected sampling
+            Some("reasoning") => {
+                let capabilities = model
+                    .entry("capabilities")
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                    .as_table_mut()
+                    .context("model capabilities is not a table")?;
+                for capability in ["system_role", "temperature"] {
+                    capabilities.insert(capability.to_owned(), toml::Value::Boolean(false));
+                }
+            }
+            _ => anyhow::bail!("unknown model class: {class}"),
+        }
+    }
+    Ok(())
+}
+
 fn get_anthropic_models_from_file(
     config_file: &Path,
     generate_missing: bool,
@@ -66,7 +529,8 @@ fn get_anthropic_models_from_file(
 
 #[cfg(test)]
 mod config_tests {
-    use super::Models;
+    use super::{migrate_model_classes, patch_models_toml, ModelEntry, ModelField, Models};
+    use crate::api::{Parameter, Provider, RemoteModel};
     use std::path::Path;
 
     #[test]
@@ -74,4 +538,97 @@ mod config_tests {
         let models_dir = Path::new("models");
         Models::from_disk(models_dir, false).expect("load openai models from template");
     }
+
+    #[test]
+    fn legacy_model_class() {
+        let mut models_toml: toml::Table = toml::from_str(
+            "[[models]]\nid = \"o1\"\nclass = \"reasoning\"\n\n\
+            [[models]]\nid = \"gpt\"\nclass = \"classic\"\n",
+        )
+        .expect("parse models");
+        migrate_model_classes(&mut models_toml).expect("migrate classes");
+        let models = models_toml["models"].as_array().expect("models");
+        assert_eq!(
+            models[0].get("capabilities").map(ToString::to_string),
+            Some("{ system_role = false, temperature = false }".to_owned())
+        );
+        assert!(models[1].get("class").is_none());
+        assert!(models[1].get("capabilities").is_none());
+        let mut unknown: toml::Table =
+            toml::from_str("[[models]]\nclass = \"magic\"\n").expect("parse models");
+        assert!(migrate_model_classes(&mut unknown).is_err());
+    }
+
+    #[test]
+    fn patch_models_in_place() {
+        let existing = "# Header\n\n[[models]]\nid = \"a\"\ntemperature = 0.5 # Warm\n\n\
+            [[models]]\nid = \"b\"\nname = \"B\"  # Kept\n";
+        let updated = "[[models]]\nid = \"a\"\ntemperature = 0.7\n\n\
+            [[models]]\nid = \"c\"\n\n[[models]]\nid = \"b\"\nname = \"B\"\n";
+        assert_eq!(
+            patch_models_toml(existing, updated).expect("patch models"),
+            "# Header\n\n[[models]]\nid = \"a\"\ntemperature = 0.7 # Warm\n\n\
+            [[models]]\nid = \"c\"\n\n[[models]]\nid = \"b\"\nname = \"B\"  # Kept\n"
+        );
+        let updated = "[[models]]\nid = \"b\"\nname = \"B\"\n";
+        assert_eq!(
+            patch_models_toml(existing, updated).expect("patch models"),
+            "# Header\n\n[[models]]\nid = \"b\"\nname = \"B\"  # Kept\n"
+        );
+    }
+
+    #[test]
+    fn edit_and_save_models() {
+        let mut models = Models::from_disk(Path::new("models"), false).expect("load models");
+        let model = models.anthropic.first_mut().expect("anthropic model");
+        model.temperature = 0.6;
+        model.step_field(ModelField::Parameter(Parameter::Temperature), true);
+        model.step_field(ModelField::ContextWindow, false);
+        model.set_text_field(ModelField::Name, "Edited".to_owned());
+        let temp_dir = crate::test_dir();
+        let models_dir = temp_dir.path();
+        for provider in [Provider::OpenAi, Provider::Anthropic] {
+            models.to_disk(models_dir, provider).expect("save models");
+        }
+        let anthropic_toml =
+            std::fs::read_to_string(models_dir.join("anthropic.toml")).expect("read models");
+        let saved = Models::from_disk(models_dir, false).expect("reload models");
+        assert!(anthropic_toml.starts_with("# For details"));
+        assert!(anthropic_toml.contains("temperature = 0.7\n"));
+        assert_eq!(saved.openai.len(), models.openai.len());
+        let model = saved.anthropic.first().expect("saved anthropic model");
+        assert_eq!(model.name, "Edited");
+        assert_eq!(model.context_window, Some(200_000 - 8192));
+    }
+
+    #[test]
+    fn unconfigured_remote_models() {
+        let mut models = Models::from_disk(Path::new("models"), false).expect("load models");
+        let configured_id = models.openai.first().expect("openai model").id.clone();
+        let remote_models = [configured_id, "o9-preview".to_owned()].map(|id| RemoteModel {
+            name: id.clone(),
+            id,
+        });
+        let unconfigured = models.get_unconfigured(Provider::OpenAi, &remote_models);
+        assert_eq!(unconfigured, vec![&remote_models[1]]);
+        let new_model = unconfigured[0].clone();
+        assert!(!models.add_remote(Provider::OpenAi, &new_model));
+        let added = models.openai.last().expect("added model");
+        assert!(added.capabilities.developer_role);
+        assert!(!added.capabilities.temperature);
+        assert!(models
+            .get_unconfigured(Provider::OpenAi, &remote_models)
+            .is_empty());
+        // Models from the template keep their context window and pricing
+        let template_model = models.openai.remove(0);
+        assert!(models.add_remote(
+            Provider::OpenAi,
+            &RemoteModel {
+                id: template_model.id.clone(),
+                name: template_model.id.clone(),
+            }
+        ));
+        let added = models.openai.last().expect("added model");
+        assert_eq!(added.context_window, template_model.context_window);
+    }
 }
@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+const NAMED_FILE_EXTENSION: &str = "md";
+
+/// A text file named after its contents, like snippets and system instructions presets.
+#[derive(Debug, Clone)]
+pub struct NamedFile {
+    pub name: String,
+    pub text: String,
+}
+
+/// Check if the name can be used as the file name of a named file.
+#[must_use]
+pub fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty() && !name.starts_with('.') && !name.contains(['/', '\\', ':', '\0'])
+}
+
+#[must_use]
+pub fn get_named_file(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.{NAMED_FILE_EXTENSION}"))
+}
+
+/// Read the named files in the directory sorted by name, files with invalid names are skipped.
+pub fn read_named_files(dir: &Path) -> Result<Vec<NamedFile>> {
+    let mut files = Vec::new();
+    for entry in
+        std::fs::read_dir(dir).with_context(|| format!("read directory {}", dir.display()))?
+    {
+        let path = entry.context("read directory entry")?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some(NAMED_FILE_EXTENSION) {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        if !is_valid_name(name) {
+            continue;
+        }
+        let text =
+            std::fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+        files.push(NamedFile {
+            name: name.to_owned(),
+            text,
+        });
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+#[cfg(test)]
+mod named_files_tests {
+    use super::{get_named_file, is_valid_name, read_named_files};
+
+    #[test]
+    fn names() {
+        assert!(is_valid_name("Code review"));
+        assert!(!is_valid_name("../config"));
+        assert!(!is_valid_name(".hidden"));
+        assert!(!is_valid_name(" "));
+    }
+
+    #[test]
+    fn read_sorted() {
+        let dir = crate::test_dir();
+        let dir = dir.path();
+        std::fs::write(get_named_file(dir, "b"), "second").expect("write file");
+        std::fs::write(get_named_file(dir, "a"), "first").expect("write file");
+        std::fs::write(get_named_file(dir, ".hidden"), "hidden").expect("write file");
+        std::fs::write(dir.join("c.txt"), "other").expect("write file");
+        let files = read_named_files(dir).expect("read files");
+        let files: Vec<_> = files
+            .iter()
+            .map(|file| (file.name.as_str(), file.text.as_str()))
+            .collect();
+        assert_eq!(files, vec![("a", "first"), ("b", "second")]);
+    }
+}
@@ -0,0 +1,126 @@
+use super::named_files::{get_named_file, read_named_files};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// A reusable prompt fragment, stored as a file in the snippets directory.
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    pub name: String,
+    pub text: String,
+}
+
+impl Snippet {
+    #[must_use]
+    pub fn preview(&self, length: usize) -> String {
+        crate::preview(&self.text, length)
+    }
+
+    #[must_use]
+    pub fn file_path(&self, snippets_dir: &Path) -> PathBuf {
+        get_named_file(snippets_dir, &self.name)
+    }
+
+    pub fn to_file(&self, snippets_dir: &Path) -> Result<()> {
+        std::fs::write(self.file_path(snippets_dir), &self.text)
+            .with_context(|| format!("write snippet {}", self.name))
+    }
+}
+
+/// Load the snippets from the snippets directory sorted by name.
+pub fn load_snippets(snippets_dir: &Path) -> Result<Vec<Snippet>> {
+    let snippets = read_named_files(snippets_dir)
+        .context("read snippets")?
+        .into_iter()
+        .map(|file| Snippet {
+            name: file.name,
+            text: file.text,
+        })
+        .collect();
+    Ok(snippets)
+}
+
+/// Indices of the snippets matching the query, best matches first.
+///
+/// A snippet matches if the characters of the query appear in order in its name or text, matches
+/// in the name and consecutive characters score higher.
+#[must_use]
+pub fn filter_snippets(snippets: &[Snippet], query: &str) -> Vec<usize> {
+    let mut matches: Vec<(usize, usize)> = snippets
+        .iter()
+        .enumerate()
+        .filter_map(|(index, snippet)| {
+            let name_score = fuzzy_score(&snippet.name, query).map(|score| score.saturating_mul(2));
+            let text_score = fuzzy_score(&snippet.text, query);
+            name_score.max(text_score).map(|score| (index, score))
+        })
+        .collect();
+    matches.sort_by(|(a_index, a_score), (b_index, b_score)| {
+        b_score.cmp(a_score).then(a_index.cmp(b_index))
+    });
+    matches.into_iter().map(|(index, _)| index).collect()
+}
+
+/// Score how well the query matches the text as a case-insensitive subsequence, `None` if it
+/// does not match.
+fn fuzzy_score(text: &str, query: &str) -> Option<usize> {
+    let mut text_chars = text.chars().flat_map(char::to_lowercase);
+    let mut score = 1;
+    let mut streak = 0;
+    for query_char in query.chars().flat_map(char::to_lowercase) {
+        if query_char.is_whitespace() {
+            continue;
+        }
+        let mut skipped = false;
+        loop {
+            let text_char = text_chars.next()?;
+            if text_char == query_char {
+                break;
+            }
+            skipped = true;
+        }
+        streak = if skipped { 1 } else { streak + 1 };
+        score += streak;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod snippets_tests {
+    use super::{filter_snippets, load_snippets, Snippet};
+
+    #[test]
+    fn fuzzy_filter() {
+        let snippets = [
+            ("Explain Rust", "Explain like I'm new to Rust."),
+            ("Review diff", "Review this diff for bugs."),
+            ("Rewrite", "Rewrite this to be more concise."),
+        ]
+        .map(|(name, text)| Snippet {
+            name: name.to_owned(),
+            text: text.to_owned(),
+        });
+        assert_eq!(filter_snippets(&snippets, ""), vec![0, 1, 2]);
+        assert_eq!(filter_snippets(&snippets, "rvw"), vec![1]);
+        assert_eq!(filter_snippets(&snippets, "RE"), vec![1, 2]);
+        assert_eq!(filter_snippets(&snippets, "bugs"), vec![1]);
+        assert!(filter_snippets(&snippets, "xyz").is_empty());
+    }
+
+    #[test]
+    fn snippet_files() {
+        let temp_dir = crate::test_dir();
+        let snippets_dir = temp_dir.path();
+        for name in ["Second", "First"] {
+            Snippet {
+                name: name.to_owned(),
+                text: format!("{name} snippet"),
+            }
+            .to_file(snippets_dir)
+            .expect("write snippet");
+        }
+        let snippets = load_snippets(snippets_dir).expect("load snippets");
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].name, "First");
+        assert_eq!(snippets[1].text, "Second snippet");
+    }
+}
@@ -1,7 +1,12 @@
+use super::named_files::{get_named_file, read_named_files};
+use anyhow::{Context, Result};
 use serde::Deserialize;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct System {
+    /// Presets used to populate an empty system directory, afterwards the presets are loaded
+    /// from the files in the directory.
     pub instructions: Vec<SystemInstructions>,
 }
 
@@ -15,10 +20,119 @@ pub struct SystemInstructions {
 impl SystemInstructions {
     #[must_use]
     pub fn preview(&self, length: usize) -> String {
-        self.message
-            .chars()
-            .take(length)
-            .map(|char| if char == '\n' { ' ' } else { char })
-            .collect()
+        crate::preview(&self.message, length)
+    }
+
+    #[must_use]
+    pub fn file_path(&self, system_dir: &Path) -> PathBuf {
+        get_named_file(system_dir, &self.name)
+    }
+
+    pub fn to_file(&self, system_dir: &Path) -> Result<()> {
+        std::fs::write(self.file_path(system_dir), &self.message)
+            .with_context(|| format!("write system instructions preset {}", self.name))
+    }
+}
+
+/// Load the presets from the system directory, the directory is populated with the default
+/// presets if it contains none.
+///
+/// Presets are in the order of the defaults, followed by other presets sorted by name. The first
+/// preset is used for new conversations.
+pub fn load_presets(
+    system_dir: &Path,
+    defaults: &[SystemInstructions],
+) -> Result<Vec<SystemInstructions>> {
+    let mut presets = read_presets(system_dir)?;
+    if presets.is_empty() {
+        for preset in defaults {
+            preset.to_file(system_dir)?;
+        }
+        presets = read_presets(system_dir)?;
+    }
+    presets.sort_by_key(|preset| {
+        defaults
+            .iter()
+            .position(|default| default.name == preset.name)
+            .unwrap_or(usize::MAX)
+    });
+    Ok(presets)
+}
+
+/// Describe how the configured presets differ from the presets loaded from the directory, since
+/// the configured presets only populate an empty directory.
+#[must_use]
+pub fn get_preset_differences(
+    configured: &[SystemInstructions],
+    presets: &[SystemInstructions],
+) -> Vec<String> {
+    configured
+        .iter()
+        .filter_map(|configured| {
+            match presets.iter().find(|preset| preset.name == configured.name) {
+                None => Some(format!(
+                    "Preset '{}' from the config is not in the system directory",
+                    configured.name
+                )),
+                Some(preset) if preset.message.trim() != configured.message.trim() => {
+                    Some(format!(
+                        "Preset '{}' in the system directory differs from the config",
+                        configured.name
+                    ))
+                }
+                Some(_) => None,
+            }
+        })
+        .collect()
+}
+
+fn read_presets(system_dir: &Path) -> Result<Vec<SystemInstructions>> {
+    let presets = read_named_files(system_dir)
+        .context("read system instructions presets")?
+        .into_iter()
+        .map(|file| SystemInstructions {
+            name: file.name,
+            message: file.text,
+        })
+        .collect();
+    Ok(presets)
+}
+
+/// Get a preset name based on the given name that is not used by the presets.
+#[must_use]
+pub fn get_unused_preset_name(presets: &[SystemInstructions], name: &str) -> String {
+    let is_used = |candidate: &str| presets.iter().any(|preset| preset.name == candidate);
+    if !is_used(name) {
+        return name.to_owned();
+    }
+    (2..=presets.len().saturating_add(2))
+        .map(|number| format!("{name} {number}"))
+        .find(|candidate| !is_used(candidate))
+        .unwrap_or_else(|| name.to_owned())
+}
+
+#[cfg(test)]
+mod system_tests {
+    use super::{get_preset_differences, get_unused_preset_name, load_presets, SystemInstructions};
+
+    #[test]
+    fn presets() {
+        let temp_dir = crate::test_dir();
+        let system_dir = temp_dir.path();
+        let defaults = ["Quick", "Detailed"].map(|name| SystemInstructions {
+            name: name.to_owned(),
+            message: format!("{name} instructions"),
+        });
+        let presets = load_presets(system_dir, &defaults).expect("load default presets");
+        assert_eq!(presets[0].name, "Quick");
+        assert_eq!(presets[1].message, "Detailed instructions");
+        assert!(get_preset_differences(&defaults, &presets).is_empty());
+        std::fs::remove_file(presets[0].file_path(system_dir)).expect("delete preset");
+        let presets = load_presets(system_dir, &defaults).expect("load presets");
+        assert_eq!(presets.len(), 1);
+        assert_eq!(get_preset_differences(&defaults, &presets).len(), 1);
+
+        assert_eq!(get_unused_preset_name(&presets, "Detailed"), "Detailed 2");
+        assert_eq!(get_unused_preset_name(&presets, "New"), "New");
     }
 }
@@ -5,11 +5,42 @@ use serde::Deserialize;
 pub struct Ui {
     pub layout: Layout,
     pub colors: Colors,
+    #[serde(default)]
+    pub highlight: Highlight,
 }
 
+/// Options missing from older config files default to the template values.
 #[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
 pub struct Layout {
     pub prompt_size: u16,
+    pub sidebar: bool,
+    pub sidebar_width: u16,
+    pub message_metadata: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            prompt_size: 8,
+            sidebar: false,
+            sidebar_width: 30,
+            message_metadata: true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Highlight {
+    pub theme: String,
+}
+
+impl Default for Highlight {
+    fn default() -> Self {
+        Self {
+            theme: "base16-eighties.dark".to_owned(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
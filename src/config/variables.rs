@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// User-defined template variables, these take precedence over the builtin variables.
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(transparent)]
+pub struct Variables(HashMap<String, Variable>);
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Variable {
+    Value(String),
+    /// Output of a command, run every time the variable is expanded.
+    Command {
+        command: Vec<String>,
+    },
+}
+
+impl Variables {
+    /// Get the value of a user-defined or builtin variable, missing if there is no such variable.
+    pub fn resolve(&self, name: &str) -> Result<Option<String>> {
+        match self.0.get(name) {
+            Some(Variable::Value(value)) => Ok(Some(value.clone())),
+            Some(Variable::Command { command }) => run_command(command).map(Some),
+            None => resolve_builtin(name),
+        }
+    }
+}
+
+fn resolve_builtin(name: &str) -> Result<Option<String>> {
+    let now = chrono::Local::now();
+    let value = match name {
+        "date" => now.format("%Y-%m-%d").to_string(),
+        "time" => now.format("%H:%M").to_string(),
+        "datetime" => now.format("%Y-%m-%d %H:%M").to_string(),
+        "weekday" => now.format("%A").to_string(),
+        "cwd" => std::env::current_dir()
+            .context("get current directory")?
+            .display()
+            .to_string(),
+        "os" => std::env::consts::OS.to_owned(),
+        "git_branch" => run_command(&["git", "rev-parse", "--abbrev-ref", "HEAD"])?,
+        "username" => std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .context("get username from environment")?,
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+fn run_command<T: AsRef<str>>(command: &[T]) -> Result<String> {
+    let mut command_iter = command.iter().map(AsRef::as_ref);
+    let mut child = Command::new(command_iter.next().context("variable command empty")?)
+        .args(command_iter)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("run variable command")?;
+    let mut stdout = child.stdout.take().context("get variable command output")?;
+    let reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().context("wait for variable command")? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            child.kill().context("kill variable command")?;
+            child.wait().context("wait for killed variable command")?;
+            anyhow::bail!("variable command timed out after {COMMAND_TIMEOUT:?}");
+        }
+        std::thread::sleep(COMMAND_POLL_INTERVAL);
+    };
+    if !status.success() {
+        anyhow::bail!("variable command failed: {status}");
+    }
+    let output = reader
+        .join()
+        .map_err(|_| anyhow::anyhow!("variable command output reader panicked"))?
+        .context("read variable command output")?;
+    Ok(String::from_utf8_lossy(&output).trim().to_owned())
+}
@@ -2,10 +2,65 @@ pub mod api;
 pub mod app;
 pub mod chat;
 pub mod config;
+pub mod usage;
+
+use anyhow::{Context, Result};
+use std::path::Path;
 
 const APP_TITLE: &str = "MockingParrot";
 const APP_TITLE_FULL: &str = "MockingParrot AI Chat Client";
 
+/// A temporary directory for tests, removed when dropped.
+#[cfg(test)]
+fn test_dir() -> tempfile::TempDir {
+    tempfile::Builder::new()
+        .prefix("mockingparrot-")
+        .tempdir()
+        .expect("create temporary directory")
+}
+
 fn get_timestamp() -> String {
     format!("{}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"))
 }
+
+/// The first characters of the text on a single line, for previews in lists.
+fn preview(text: &str, length: usize) -> String {
+    text.chars()
+        .take(length)
+        .map(|char| if char == '\n' { ' ' } else { char })
+        .collect()
+}
+
+/// Write to a temporary file first, so an interrupted write does not corrupt the file.
+fn write_file(path: &Path, data: &str) -> Result<()> {
+    let temp_path = path.with_extension("tmp");
+    std::fs::write(&temp_path, data).context("write temporary file")?;
+    std::fs::rename(&temp_path, path).context("replace file")
+}
+
+/// Parse a file of JSON lines, a missing file has no lines.
+///
+/// Lines that fail to parse are skipped, returning their errors with the parsed values.
+fn read_json_lines<T: serde::de::DeserializeOwned>(path: &Path) -> Result<(Vec<T>, Vec<String>)> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(error).with_context(|| format!("read {}", path.display())),
+    };
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(value) => values.push(value),
+            Err(error) => errors.push(format!(
+                "Skipped line {} of {}: {error}",
+                index + 1,
+                path.display()
+            )),
+        }
+    }
+    Ok((values, errors))
+}
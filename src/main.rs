@@ -1,4 +1,8 @@
 use anyhow::{Context, Result};
+use ratatui::crossterm::event::{
+    DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+};
+use ratatui::crossterm::execute;
 use ratatui::prelude::{Backend, Terminal};
 
 use mockingparrot::app::events;
@@ -17,9 +21,21 @@ async fn main() {
 async fn run() -> Result<()> {
     let state = State::new().context("new app state")?;
     let mut terminal = ratatui::init();
+    let enable_result = execute!(std::io::stdout(), EnableMouseCapture, EnableBracketedPaste)
+        .context("enable mouse capture and bracketed paste");
+    if enable_result.is_err() {
+        ratatui::restore();
+        return enable_result;
+    }
     let app_result = run_app(&mut terminal, state).await;
+    let disable_result = execute!(
+        std::io::stdout(),
+        DisableMouseCapture,
+        DisableBracketedPaste
+    )
+    .context("disable mouse capture and bracketed paste");
     ratatui::restore();
-    app_result
+    app_result.and(disable_result)
 }
 
 async fn run_app(terminal: &mut Terminal<impl Backend>, mut state: State) -> Result<()> {
@@ -33,8 +49,17 @@ async fn run_app(terminal: &mut Terminal<impl Backend>, mut state: State) -> Res
         {
             events::HandleEventResult::None => (),
             events::HandleEventResult::Redraw => terminal.clear().context("clear terminal")?,
-            events::HandleEventResult::Quit => return Ok(()),
-        };
+            events::HandleEventResult::Quit => return state.save_session(),
+        }
         state.fix_clamp_ui_selections();
+        state.sync_prompt_draft().context("sync prompt draft")?;
+        state
+            .collect_generated_titles()
+            .await
+            .context("collect generated titles")?;
+        state
+            .collect_remote_models()
+            .await
+            .context("collect remote models")?;
     }
 }
@@ -0,0 +1,314 @@
+use crate::api::{Provider, TokenUsage};
+use crate::chat::Metadata;
+use crate::config::Limits;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A single completion recorded in the ledger.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: DateTime<Utc>,
+    pub provider: Provider,
+    pub model_id: String,
+    pub usage: TokenUsage,
+    /// Missing if the model has no configured pricing.
+    pub cost: Option<f64>,
+}
+
+impl LedgerEntry {
+    /// Create an entry from the metadata of a completion, if it has usage details.
+    #[must_use]
+    pub fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        Some(Self {
+            timestamp: metadata.created_at.unwrap_or_else(Utc::now),
+            provider: metadata.provider?,
+            model_id: metadata.model_id.clone()?,
+            usage: metadata.usage?,
+            cost: metadata.cost,
+        })
+    }
+
+    #[must_use]
+    pub fn local_date(&self) -> NaiveDate {
+        self.timestamp.with_timezone(&Local).date_naive()
+    }
+}
+
+/// Accumulated usage of several completions.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UsageTotals {
+    pub requests: u32,
+    pub usage: TokenUsage,
+    pub cost: f64,
+}
+
+impl UsageTotals {
+    pub fn add(&mut self, entry: &LedgerEntry) {
+        self.requests = self.requests.saturating_add(1);
+        self.usage += entry.usage;
+        self.cost += entry.cost.unwrap_or_default();
+    }
+}
+
+/// Usage of a provider compared against its configured limits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitStatus {
+    WithinLimits,
+    /// Usage reached the warning ratio of a limit.
+    Warning(String),
+    Exceeded(String),
+}
+
+/// Cumulative record of all completions, stored as JSON lines.
+#[derive(Debug)]
+pub struct Ledger {
+    path: PathBuf,
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl Ledger {
+    /// Load the ledger, returning the errors of entries that failed to parse and were skipped.
+    pub fn from_file(path: &Path) -> Result<(Self, Vec<String>)> {
+        let (entries, errors) = crate::read_json_lines(path).context("read ledger file")?;
+        let ledger = Self {
+            path: path.to_owned(),
+            entries,
+        };
+        Ok((ledger, errors))
+    }
+
+    /// Add an entry and append it to the ledger file.
+    pub fn record(&mut self, entry: LedgerEntry) -> Result<()> {
+        let mut line = serde_json::to_string(&entry).context("serialize ledger entry")?;
+        line.push('\n');
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .context("open ledger file")?
+            .write_all(line.as_bytes())
+            .context("write ledger entry")?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Totals of entries from the given local date onward, optionally of a single provider.
+    #[must_use]
+    pub fn totals_since(&self, date: NaiveDate, provider: Option<Provider>) -> UsageTotals {
+        let mut totals = UsageTotals::default();
+        for entry in self.entries.iter().filter(|entry| {
+            entry.local_date() >= date && provider.is_none_or(|provider| entry.provider == provider)
+        }) {
+            totals.add(entry);
+        }
+        totals
+    }
+
+    /// Compare today's and this month's usage of the provider against its limits.
+    #[must_use]
+    pub fn check_limits(&self, provider: Provider, limits: &Limits) -> LimitStatus {
+        let today = Local::now().date_naive();
+        let month_start = today.with_day(1).unwrap_or(today);
+        let daily = self.totals_since(today, Some(provider));
+        let monthly = self.totals_since(month_start, Some(provider));
+        let provider_limits = limits.get(provider);
+        let checks = [
+            (
+                "daily spending",
+                daily.cost,
+                provider_limits.daily_cost,
+                true,
+            ),
+            (
+                "monthly spending",
+                monthly.cost,
+                provider_limits.monthly_cost,
+                true,
+            ),
+            (
+                "daily token",
+                f64::from(daily.usage.total),
+                provider_limits.daily_tokens.map(f64::from),
+                false,
+            ),
+            (
+                "monthly token",
+                f64::from(monthly.usage.total),
+                provider_limits.monthly_tokens.map(f64::from),
+                false,
+            ),
+        ];
+        let mut status = LimitStatus::WithinLimits;
+        for (name, used, limit, is_cost) in checks {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let format_amount = |amount: f64| {
+                if is_cost {
+                    format_cost(amount)
+                } else {
+                    format!("{amount:.0} tokens")
+                }
+            };
+            let amounts = format!("{} / {}", format_amount(used), format_amount(limit));
+            if used >= limit {
+                return LimitStatus::Exceeded(format!(
+                    "Reached {name} limit for {provider}: {amounts}"
+                ));
+            }
+            if used >= limit * limits.warn_ratio && status == LimitStatus::WithinLimits {
+                status = LimitStatus::Warning(format!(
+                    "Approaching {name} limit for {provider}: {amounts}"
+                ));
+            }
+        }
+        status
+    }
+
+    /// Totals of each model per local date.
+    #[must_use]
+    pub fn totals_by_day_and_model(&self) -> BTreeMap<(NaiveDate, String), UsageTotals> {
+        let mut totals: BTreeMap<(NaiveDate, String), UsageTotals> = BTreeMap::new();
+        for entry in &self.entries {
+            totals
+                .entry((entry.local_date(), entry.model_id.clone()))
+                .or_default()
+                .add(entry);
+        }
+        totals
+    }
+}
+
+#[must_use]
+pub fn format_cost(cost: f64) -> String {
+    format!("${cost:.4}")
+}
+
+#[cfg(test)]
+mod usage_tests {
+    use super::{Ledger, LedgerEntry, LimitStatus};
+    use crate::api::{Pricing, Provider, TokenUsage};
+    use crate::config::{Limits, ProviderLimits};
+    use chrono::{Local, TimeZone, Utc};
+    use std::path::PathBuf;
+
+    #[test]
+    fn pricing_cost() {
+        let pricing = Pricing {
+            input: 2.0,
+            output: 10.0,
+            cached_input: Some(1.0),
+        };
+        let usage = TokenUsage {
+            prompt: 1_000_000,
+            completion: 500_000,
+            total: 1_500_000,
+            cached: 500_000,
+        };
+        assert!((pricing.cost(&usage) - 6.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn totals_by_day_and_model() {
+        let entry = |day: u32, model_id: &str, cost: Option<f64>| LedgerEntry {
+            timestamp: Local
+                .with_ymd_and_hms(2024, 1, day, 12, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc),
+            provider: Provider::OpenAi,
+            model_id: model_id.to_owned(),
+            usage: TokenUsage::default(),
+            cost,
+        };
+        let ledger = Ledger {
+            path: PathBuf::new(),
+            entries: vec![
+                entry(1, "a", Some(1.0)),
+                entry(1, "a", None),
+                entry(1, "b", Some(2.0)),
+                entry(2, "a", Some(4.0)),
+            ],
+        };
+        let totals = ledger.totals_by_day_and_model();
+        assert_eq!(totals.len(), 3);
+        let first = totals.values().next().expect("first day totals");
+        assert_eq!(first.requests, 2);
+        assert!((first.cost - 1.0).abs() < f64::EPSILON);
+        let since = ledger.totals_since(entry(2, "a", None).local_date(), None);
+        assert!((since.cost - 4.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn skip_invalid_entries() {
+        let temp_dir = crate::test_dir();
+        let path = temp_dir.path().join("ledger.jsonl");
+        let mut ledger = Ledger {
+            path: path.clone(),
+            entries: Vec::new(),
+        };
+        for cost in [Some(1.0), None] {
+            ledger
+                .record(LedgerEntry {
+                    timestamp: Utc::now(),
+                    provider: Provider::Anthropic,
+                    model_id: "a".to_owned(),
+                    usage: TokenUsage::default(),
+                    cost,
+                })
+                .expect("record entry");
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .expect("open ledger file");
+        std::io::Write::write_all(&mut file, b"{\"truncated\n").expect("write invalid entry");
+        let (loaded, errors) = Ledger::from_file(&path).expect("load ledger");
+        assert_eq!(loaded.entries.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Skipped line 3"));
+    }
+
+    #[test]
+    fn check_limits() {
+        let mut ledger = Ledger {
+            path: PathBuf::new(),
+            entries: vec![LedgerEntry {
+                timestamp: Utc::now(),
+                provider: Provider::OpenAi,
+                model_id: "a".to_owned(),
+                usage: TokenUsage::default(),
+                cost: Some(0.9),
+            }],
+        };
+        let mut limits = Limits {
+            warn_ratio: 0.8,
+            openai: ProviderLimits {
+                daily_cost: Some(1.0),
+                ..Default::default()
+            },
+            anthropic: ProviderLimits::default(),
+        };
+        assert!(matches!(
+            ledger.check_limits(Provider::OpenAi, &limits),
+            LimitStatus::Warning(_)
+        ));
+        assert_eq!(
+            ledger.check_limits(Provider::Anthropic, &limits),
+            LimitStatus::WithinLimits
+        );
+        limits.warn_ratio = 1.0;
+        assert_eq!(
+            ledger.check_limits(Provider::OpenAi, &limits),
+            LimitStatus::WithinLimits
+        );
+        ledger.entries[0].cost = Some(1.0);
+        assert!(matches!(
+            ledger.check_limits(Provider::OpenAi, &limits),
+            LimitStatus::Exceeded(_)
+        ));
+    }
+}